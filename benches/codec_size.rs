@@ -0,0 +1,55 @@
+//! Compares the size of envelopes in the binary codec with their JSON encoding.
+//!
+//! Run with `cargo bench --bench codec_size`.
+
+use cmrdts::codec;
+use cmrdts::core::{ActorId, AddCtx, Dot, Envelope, LOGICAL_BITS, VClock};
+use cmrdts::or_set::{self, ORSet};
+
+// An add from the first of `actors` replicas, each of which has issued about a
+// hundred ops, with counters that are either logical or physical timestamps.
+fn envelope(actors: u64, physical: bool) -> Envelope<ORSet<String>> {
+    let counter = |i: u64| {
+        if physical {
+            (1_700_000_000_000 + i) << LOGICAL_BITS
+        } else {
+            100 + i
+        }
+    };
+    // Actor ids are random 64-bit values in practice.
+    let actor = |i: u64| ActorId(0x9e37_79b9_7f4a_7c15u64.wrapping_mul(i + 1));
+
+    let clock = VClock((0..actors).map(|i| (actor(i), counter(i))).collect());
+    let dot = Dot {
+        actor: actor(0),
+        counter: counter(0),
+    };
+    let ctx = AddCtx {
+        dot,
+        prev: dot.counter - 1,
+        clock,
+    };
+    Envelope::new(or_set::Op::Add(String::from("milk")), ctx)
+}
+
+fn main() {
+    println!(
+        "{:>8} {:>10} {:>10} {:>10} {:>8}",
+        "actors", "clock", "json", "codec", "ratio"
+    );
+    for physical in [false, true] {
+        for actors in [1, 4, 16, 64, 256] {
+            let envelope = envelope(actors, physical);
+            let json = serde_json::to_vec(&envelope).unwrap().len();
+            let binary = codec::encode(&envelope).len();
+            println!(
+                "{:>8} {:>10} {:>10} {:>10} {:>7.1}%",
+                actors,
+                if physical { "physical" } else { "logical" },
+                json,
+                binary,
+                100.0 * binary as f64 / json as f64
+            );
+        }
+    }
+}
@@ -16,6 +16,7 @@ fn main() {
             actor: server1_id,
             counter: 1,
         },
+        prev: 0,
         clock: VClock::default(),
     };
     server1_views.apply(cmrdts::g_counter::Op::Inc(10), server1_op_ctx);
@@ -27,6 +28,7 @@ fn main() {
             actor: server2_id,
             counter: 1,
         },
+        prev: 0,
         clock: VClock::default(),
     };
     server2_views.apply(cmrdts::g_counter::Op::Inc(15), server2_op_ctx);
@@ -18,6 +18,7 @@ fn main() {
             actor: alice_id,
             counter: 1,
         },
+        prev: 0,
         clock: VClock::default(),
     };
     alice_counter.apply(cmrdts::pn_counter::Op::Inc(5), alice_op_ctx);
@@ -29,6 +30,7 @@ fn main() {
             actor: bob_id,
             counter: 1,
         },
+        prev: 0,
         clock: VClock::default(),
     };
     let bob_op_2_ctx = AddCtx {
@@ -36,6 +38,7 @@ fn main() {
             actor: bob_id,
             counter: 2,
         },
+        prev: 1,
         clock: VClock::default(),
     };
     bob_counter.apply(cmrdts::pn_counter::Op::Inc(2), bob_op_1_ctx);
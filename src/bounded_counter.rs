@@ -0,0 +1,317 @@
+use crate::{
+    ActorId, Error, VClock,
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, Replica},
+    g_counter::{self, GCounter},
+    pn_counter::{self, PNCounter},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A counter that never drops below zero, built on a `PNCounter` and per-actor rights.
+///
+/// Every increment grants its actor the right to decrement by the same amount,
+/// and rights can be handed to another actor with a `Transfer`. An actor may only
+/// decrement or transfer the rights it holds, so as long as every replica checks
+/// its own operations, the sum of all decrements can never exceed the sum of all
+/// increments, without any coordination. Local operations are therefore applied
+/// with [`Replica::try_apply`], which returns an error for an operation the
+/// replica lacks the rights for. It does not implement
+/// [`InfallibleLocal`](crate::core::InfallibleLocal), so [`Replica::apply`] is
+/// not available.
+///
+/// Rights are only checked locally. A replica may learn of a remote operation
+/// before the transfers that granted its rights, so remote operations are not
+/// rejected for lacking them. A peer that skips the check can still take the
+/// value below zero, and [`CmRDT::read`] then reports the negative value as is.
+///
+/// The transfers received by each actor are kept in a `GCounter`, where each
+/// transfer is an increment tagged with the sender's dot, so that
+/// [`CmRDT::purge`] folds the stable ones into a total per sender.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BoundedCounter {
+    pub counter: PNCounter,
+    /// The transfers of rights, keyed by the actor that received them. The
+    /// sender of each transfer is the actor of its dot.
+    pub transfers: BTreeMap<ActorId, GCounter>,
+}
+
+/// Operations for a BoundedCounter can increment, decrement, or transfer rights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op {
+    Inc(u64),
+    Dec(u64),
+    /// Hands the given amount of rights to another actor.
+    Transfer(ActorId, u64),
+}
+
+impl BoundedCounter {
+    /// Returns the rights currently held by the given actor.
+    pub fn rights(&self, actor: &ActorId) -> u64 {
+        let received = self
+            .transfers
+            .get(actor)
+            .map_or(0, |transfers| transfers.read());
+        let sent: u64 = self
+            .transfers
+            .values()
+            .map(|transfers| transfers.actor_value(actor))
+            .sum();
+
+        let granted = self.counter.increments.actor_value(actor) + received;
+        let spent = self.counter.decrements.actor_value(actor) + sent;
+        granted.saturating_sub(spent)
+    }
+
+    /// Returns the recipient and amount of the transfer with the given dot, if
+    /// it is still recorded individually.
+    fn transfer(&self, ctx: &AddCtx) -> Option<(ActorId, u64)> {
+        self.transfers
+            .iter()
+            .find_map(|(to, transfers)| Some((*to, *transfers.ops.get(&ctx.dot)?)))
+    }
+}
+
+impl CmRDT for BoundedCounter {
+    type Op = Op;
+    type Value = i64;
+
+    fn apply(&mut self, op: Self::Op, ctx: AddCtx) {
+        match op {
+            Op::Inc(amount) => self.counter.apply(pn_counter::Op::Inc(amount), ctx),
+            Op::Dec(amount) => self.counter.apply(pn_counter::Op::Dec(amount), ctx),
+            Op::Transfer(to, amount) => self
+                .transfers
+                .entry(to)
+                .or_default()
+                .apply(g_counter::Op::Inc(amount), ctx),
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.counter.merge(other.counter);
+        for (to, transfers) in other.transfers {
+            self.transfers.entry(to).or_default().merge(transfers);
+        }
+    }
+
+    fn read(&self) -> Self::Value {
+        self.counter.read()
+    }
+
+    /// Folds the stable increments, decrements and transfers into totals.
+    fn purge(&mut self, stable: &VClock) {
+        self.counter.purge(stable);
+        for transfers in self.transfers.values_mut() {
+            transfers.purge(stable);
+        }
+    }
+
+    /// Rejects an op whose dot was already recorded with a different operation.
+    fn validate(&self, op: &Self::Op, ctx: &AddCtx) -> Result<(), Error> {
+        let conflicts = match op {
+            Op::Inc(_) | Op::Dec(_) => self.transfer(ctx).is_some(),
+            Op::Transfer(to, amount) => match self.transfer(ctx) {
+                Some(transfer) => transfer != (*to, *amount),
+                None => {
+                    self.counter.increments.ops.contains_key(&ctx.dot)
+                        || self.counter.decrements.ops.contains_key(&ctx.dot)
+                }
+            },
+        };
+        if conflicts {
+            return Err(Error::ConflictingDot(ctx.dot));
+        }
+
+        match op {
+            Op::Inc(amount) => self.counter.validate(&pn_counter::Op::Inc(*amount), ctx),
+            Op::Dec(amount) => self.counter.validate(&pn_counter::Op::Dec(*amount), ctx),
+            Op::Transfer(..) => Ok(()),
+        }
+    }
+
+    /// Rejects a decrement or transfer that exceeds the actor's rights.
+    fn validate_local(&self, op: &Self::Op, actor: ActorId) -> Result<(), Error> {
+        match op {
+            Op::Dec(amount) | Op::Transfer(_, amount) if *amount > self.rights(&actor) => {
+                Err(Error::InsufficientRights(actor))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl DeltaCmRDT for BoundedCounter {
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        Self {
+            counter: self.counter.delta_since(applied),
+            transfers: self
+                .transfers
+                .iter()
+                .map(|(to, transfers)| (*to, transfers.delta_since(applied)))
+                .filter(|(_, delta)| *delta != GCounter::default())
+                .collect(),
+        }
+    }
+}
+
+impl Replica<BoundedCounter> {
+    /// Returns the rights held by this replica's actor.
+    pub fn rights(&self) -> u64 {
+        self.state().rights(&self.actor_id)
+    }
+
+    /// Hands some of this replica's rights to another actor.
+    pub fn transfer_rights(&mut self, to: ActorId, amount: u64) -> Result<(Op, AddCtx), Error> {
+        self.try_apply(Op::Transfer(to, amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_increments_grant_rights() {
+        let mut replica = Replica::new(ActorId(1), BoundedCounter::default());
+        replica.try_apply(Op::Inc(10)).unwrap();
+        replica.try_apply(Op::Dec(3)).unwrap();
+
+        assert_eq!(replica.read(), 7);
+        assert_eq!(replica.rights(), 7);
+    }
+
+    #[test]
+    fn test_decrement_without_rights_fails_locally() {
+        // Arrange
+        let mut replica = Replica::new(ActorId(1), BoundedCounter::default());
+        replica.try_apply(Op::Inc(5)).unwrap();
+        let clock = replica.clock().clone();
+
+        // Act
+        let result = replica.try_apply(Op::Dec(6));
+
+        // Assert: nothing was applied and no dot was consumed.
+        assert_eq!(result, Err(Error::InsufficientRights(ActorId(1))));
+        assert_eq!(replica.read(), 5);
+        assert_eq!(replica.clock(), &clock);
+        assert!(replica.try_apply(Op::Dec(5)).is_ok());
+        assert_eq!(replica.read(), 0);
+    }
+
+    #[test]
+    fn test_rights_are_needed_even_when_the_value_is_positive() {
+        // Arrange: A holds all of the value, B holds no rights.
+        let mut replica_a = Replica::new(ActorId(1), BoundedCounter::default());
+        let mut replica_b = Replica::new(ActorId(2), BoundedCounter::default());
+        let (op, ctx) = replica_a.try_apply(Op::Inc(10)).unwrap();
+        replica_b.apply_remote(op, ctx);
+
+        // Act & Assert
+        assert_eq!(replica_b.read(), 10);
+        assert_eq!(
+            replica_b.try_apply(Op::Dec(1)),
+            Err(Error::InsufficientRights(ActorId(2)))
+        );
+    }
+
+    #[test]
+    fn test_transfer_rights() {
+        // Arrange
+        let mut replica_a = Replica::new(ActorId(1), BoundedCounter::default());
+        let mut replica_b = Replica::new(ActorId(2), BoundedCounter::default());
+        replica_a.try_apply(Op::Inc(10)).unwrap();
+
+        // Act
+        let (op, ctx) = replica_a.transfer_rights(ActorId(2), 4).unwrap();
+        replica_b.merge(replica_a.state().clone(), replica_a.clock().clone());
+
+        // Assert
+        assert_eq!(replica_a.rights(), 6);
+        assert_eq!(replica_b.rights(), 4);
+        assert_eq!(
+            replica_a.transfer_rights(ActorId(2), 7),
+            Err(Error::InsufficientRights(ActorId(1)))
+        );
+        assert_eq!(replica_b.state().transfers[&ActorId(2)].ops[&ctx.dot], 4);
+        assert_eq!(op, Op::Transfer(ActorId(2), 4));
+    }
+
+    #[test]
+    fn test_concurrent_decrements_never_go_below_zero() {
+        // Arrange: A and B split the rights to 10 between them.
+        let mut replica_a = Replica::new(ActorId(1), BoundedCounter::default());
+        let mut replica_b = Replica::new(ActorId(2), BoundedCounter::default());
+        replica_a.try_apply(Op::Inc(10)).unwrap();
+        replica_a.transfer_rights(ActorId(2), 4).unwrap();
+        replica_b.merge(replica_a.state().clone(), replica_a.clock().clone());
+
+        // Act: both spend everything they can without hearing from each other.
+        let dec_a = replica_a.try_apply(Op::Dec(6)).unwrap();
+        let dec_b = replica_b.try_apply(Op::Dec(4)).unwrap();
+        let extra_a = replica_a.try_apply(Op::Dec(1));
+        let extra_b = replica_b.try_apply(Op::Dec(1));
+
+        replica_a.apply_remote(dec_b.0, dec_b.1);
+        replica_b.apply_remote(dec_a.0, dec_a.1);
+
+        // Assert
+        assert!(extra_a.is_err() && extra_b.is_err());
+        assert_eq!(replica_a.read(), 0);
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+
+    #[test]
+    fn test_try_apply_rejects_dot_reused_for_transfer() {
+        let mut replica = Replica::new(ActorId(1), BoundedCounter::default());
+        let (_, ctx) = replica.try_apply(Op::Inc(5)).unwrap();
+
+        let mut counter = replica.state().clone();
+        assert_eq!(
+            counter.try_apply(Op::Transfer(ActorId(2), 5), ctx.clone()),
+            Err(Error::ConflictingDot(ctx.dot))
+        );
+    }
+
+    #[test]
+    fn test_unchecked_remote_decrement_reads_negative() {
+        // Arrange: a peer that skipped the rights check decremented anyway.
+        let mut replica = Replica::new(ActorId(1), BoundedCounter::default());
+        let ctx = AddCtx {
+            dot: crate::Dot {
+                actor: ActorId(2),
+                counter: 1,
+            },
+            prev: 0,
+            clock: VClock([(ActorId(2), 1)].into()),
+        };
+
+        // Act
+        replica.apply_remote(Op::Dec(3), ctx);
+
+        // Assert
+        assert_eq!(replica.read(), -3);
+    }
+
+    #[test]
+    fn test_purge_folds_stable_transfers() {
+        // Arrange
+        let mut replica_a = Replica::new(ActorId(1), BoundedCounter::default());
+        let mut replica_b = Replica::new(ActorId(2), BoundedCounter::default());
+        replica_a.try_apply(Op::Inc(10)).unwrap();
+        for _ in 0..5 {
+            let (op, ctx) = replica_a.transfer_rights(ActorId(2), 1).unwrap();
+            replica_b.apply_remote(op, ctx);
+        }
+        replica_a.observe_peer(ActorId(2), replica_b.clock().clone());
+
+        // Act
+        replica_a.purge();
+
+        // Assert: the transfers are folded, and the rights are unchanged.
+        let transfers = &replica_a.state().transfers[&ActorId(2)];
+        assert!(transfers.ops.is_empty());
+        assert_eq!(transfers.totals[&ActorId(1)], 5);
+        assert_eq!(replica_a.rights(), 5);
+        assert_eq!(replica_a.state().rights(&ActorId(2)), 5);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::codec::{CODEC_VERSION, DecodeError};
+use crate::core::ActorId;
+
+/// Reads values from a frame produced by an [`Encoder`](crate::codec::Encoder).
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    actors: Vec<ActorId>,
+}
+
+impl<'a> Decoder<'a> {
+    /// Checks the version at the start of the frame and reads the actor table
+    /// that follows, leaving the decoder at the start of the body.
+    pub fn new(frame: &'a [u8]) -> Result<Self, DecodeError> {
+        let mut decoder = Self {
+            bytes: frame,
+            actors: Vec::new(),
+        };
+        let version = decoder.read_byte()?;
+        if version != CODEC_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let count = decoder.read_len()?;
+        decoder.actors.reserve(count);
+        for _ in 0..count {
+            let actor = ActorId(decoder.read_u64()?);
+            decoder.actors.push(actor);
+        }
+        Ok(decoder)
+    }
+
+    /// Returns the number of bytes left to read.
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Reads a single raw byte.
+    pub fn read_byte(&mut self) -> Result<u8, DecodeError> {
+        let (&byte, rest) = self.bytes.split_first().ok_or(DecodeError::UnexpectedEof)?;
+        self.bytes = rest;
+        Ok(byte)
+    }
+
+    /// Reads the given number of raw bytes.
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if len > self.bytes.len() {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    /// Reads an unsigned LEB128 varint.
+    pub fn read_u64(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_byte()?;
+            let bits = u64::from(byte & 0x7f);
+            if shift == 63 && bits > 1 {
+                return Err(DecodeError::VarintOverflow);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::VarintOverflow)
+    }
+
+    /// Reads a zigzag-encoded signed varint.
+    pub fn read_i64(&mut self) -> Result<i64, DecodeError> {
+        let value = self.read_u64()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    /// Reads a length prefix. The length is capped by the bytes left in the
+    /// frame, since every element takes at least one byte.
+    pub fn read_len(&mut self) -> Result<usize, DecodeError> {
+        let len = self.read_u64()?;
+        if len > self.bytes.len() as u64 {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        Ok(len as usize)
+    }
+
+    /// Reads an actor index and looks it up in the actor table.
+    pub fn read_actor(&mut self) -> Result<ActorId, DecodeError> {
+        let index = self.read_u64()?;
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.actors.get(i).copied())
+            .ok_or(DecodeError::UnknownActor(index))
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use crate::codec::CODEC_VERSION;
+use crate::core::ActorId;
+
+/// Writes values into a frame, collecting the actors they reference into the
+/// frame's actor table.
+#[derive(Debug, Clone, Default)]
+pub struct Encoder {
+    body: Vec<u8>,
+    actors: Vec<ActorId>,
+    indices: BTreeMap<ActorId, u64>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes a single raw byte.
+    pub fn write_byte(&mut self, byte: u8) {
+        self.body.push(byte);
+    }
+
+    /// Writes raw bytes, without a length prefix.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.body.extend_from_slice(bytes);
+    }
+
+    /// Writes an unsigned LEB128 varint.
+    pub fn write_u64(&mut self, value: u64) {
+        write_varint(&mut self.body, value);
+    }
+
+    /// Writes a signed integer as a zigzag-encoded varint, so that values close
+    /// to zero stay short whatever their sign.
+    pub fn write_i64(&mut self, value: i64) {
+        self.write_u64(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    /// Writes the index of the actor in the actor table, adding it to the table
+    /// the first time it is seen.
+    pub fn write_actor(&mut self, actor: ActorId) {
+        let next = self.actors.len() as u64;
+        let index = *self.indices.entry(actor).or_insert(next);
+        if index == next {
+            self.actors.push(actor);
+        }
+        self.write_u64(index);
+    }
+
+    /// Returns the complete frame: the version, the actor table and the body.
+    pub fn finish(self) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(self.body.len() + 2 + 4 * self.actors.len());
+        frame.push(CODEC_VERSION);
+        write_varint(&mut frame, self.actors.len() as u64);
+        for actor in &self.actors {
+            write_varint(&mut frame, actor.0);
+        }
+        frame.extend_from_slice(&self.body);
+        frame
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
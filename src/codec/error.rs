@@ -0,0 +1,48 @@
+use std::fmt;
+
+/// The errors that can occur when decoding a malformed or truncated frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The frame was written with a version of the layout this release does
+    /// not know.
+    UnsupportedVersion(u8),
+    /// The frame ended in the middle of a value.
+    UnexpectedEof,
+    /// A varint does not fit in 64 bits.
+    VarintOverflow,
+    /// An actor index points past the end of the actor table.
+    UnknownActor(u64),
+    /// An enum tag does not name any of its variants.
+    InvalidTag(u64),
+    /// A string is not valid UTF-8, or a char is not a valid scalar value.
+    InvalidUtf8,
+    /// Bytes were left over after the value was decoded.
+    TrailingBytes(usize),
+    /// A clock delta arrived before the full clock it is relative to.
+    MissingBaseline,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported codec version {version}")
+            }
+            DecodeError::UnexpectedEof => write!(f, "unexpected end of frame"),
+            DecodeError::VarintOverflow => write!(f, "varint does not fit in 64 bits"),
+            DecodeError::UnknownActor(index) => {
+                write!(f, "actor index {index} is not in the actor table")
+            }
+            DecodeError::InvalidTag(tag) => write!(f, "invalid tag {tag}"),
+            DecodeError::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            DecodeError::TrailingBytes(count) => {
+                write!(f, "{count} trailing bytes after the decoded value")
+            }
+            DecodeError::MissingBaseline => {
+                write!(f, "clock delta received before a full clock")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
@@ -0,0 +1,334 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::codec::{Decode, DecodeError, Decoder, Encode, Encoder};
+use crate::core::{ActorId, AddCtx, Batch, CmRDT, Dot, Envelope, VClock};
+
+macro_rules! varint {
+    ($($ty:ty),*) => {$(
+        impl Encode for $ty {
+            fn encode(&self, encoder: &mut Encoder) {
+                encoder.write_u64(*self as u64);
+            }
+        }
+
+        impl Decode for $ty {
+            fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                let value = decoder.read_u64()?;
+                <$ty>::try_from(value).map_err(|_| DecodeError::VarintOverflow)
+            }
+        }
+    )*};
+}
+
+macro_rules! zigzag {
+    ($($ty:ty),*) => {$(
+        impl Encode for $ty {
+            fn encode(&self, encoder: &mut Encoder) {
+                encoder.write_i64(*self as i64);
+            }
+        }
+
+        impl Decode for $ty {
+            fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                let value = decoder.read_i64()?;
+                <$ty>::try_from(value).map_err(|_| DecodeError::VarintOverflow)
+            }
+        }
+    )*};
+}
+
+varint!(u16, u32, u64, usize);
+zigzag!(i8, i16, i32, i64, isize);
+
+impl Encode for u8 {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_byte(*self);
+    }
+}
+
+impl Decode for u8 {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        decoder.read_byte()
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_byte(u8::from(*self));
+    }
+}
+
+impl Decode for bool {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        match decoder.read_byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(DecodeError::InvalidTag(tag.into())),
+        }
+    }
+}
+
+impl Encode for char {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_u64(u32::from(*self).into());
+    }
+}
+
+impl Decode for char {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let value = u32::decode(decoder)?;
+        char::from_u32(value).ok_or(DecodeError::InvalidUtf8)
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_u64(self.len() as u64);
+        encoder.write_bytes(self.as_bytes());
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.as_str().encode(encoder);
+    }
+}
+
+impl Decode for String {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let len = decoder.read_len()?;
+        let bytes = decoder.read_bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidUtf8)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, encoder: &mut Encoder) {
+        (**self).encode(encoder);
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        match self {
+            None => encoder.write_byte(0),
+            Some(value) => {
+                encoder.write_byte(1);
+                value.encode(encoder);
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        match decoder.read_byte()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(decoder)?)),
+            tag => Err(DecodeError::InvalidTag(tag.into())),
+        }
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_u64(self.len() as u64);
+        for value in self {
+            value.encode(encoder);
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.as_slice().encode(encoder);
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let len = decoder.read_len()?;
+        (0..len).map(|_| T::decode(decoder)).collect()
+    }
+}
+
+impl<T: Encode> Encode for BTreeSet<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_u64(self.len() as u64);
+        for value in self {
+            value.encode(encoder);
+        }
+    }
+}
+
+impl<T: Decode + Ord> Decode for BTreeSet<T> {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let len = decoder.read_len()?;
+        (0..len).map(|_| T::decode(decoder)).collect()
+    }
+}
+
+impl<K: Encode, V: Encode> Encode for BTreeMap<K, V> {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_u64(self.len() as u64);
+        for (key, value) in self {
+            key.encode(encoder);
+            value.encode(encoder);
+        }
+    }
+}
+
+impl<K: Decode + Ord, V: Decode> Decode for BTreeMap<K, V> {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let len = decoder.read_len()?;
+        (0..len)
+            .map(|_| Ok((K::decode(decoder)?, V::decode(decoder)?)))
+            .collect()
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.0.encode(encoder);
+        self.1.encode(encoder);
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        Ok((A::decode(decoder)?, B::decode(decoder)?))
+    }
+}
+
+impl<A: Encode, B: Encode, C: Encode> Encode for (A, B, C) {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.0.encode(encoder);
+        self.1.encode(encoder);
+        self.2.encode(encoder);
+    }
+}
+
+impl<A: Decode, B: Decode, C: Decode> Decode for (A, B, C) {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        Ok((
+            A::decode(decoder)?,
+            B::decode(decoder)?,
+            C::decode(decoder)?,
+        ))
+    }
+}
+
+impl Encode for ActorId {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_actor(*self);
+    }
+}
+
+impl Decode for ActorId {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        decoder.read_actor()
+    }
+}
+
+impl Encode for Dot {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_actor(self.actor);
+        encoder.write_u64(self.counter);
+    }
+}
+
+impl Decode for Dot {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        Ok(Dot {
+            actor: decoder.read_actor()?,
+            counter: decoder.read_u64()?,
+        })
+    }
+}
+
+impl Encode for VClock {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.0.encode(encoder);
+    }
+}
+
+impl Decode for VClock {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        Ok(VClock(BTreeMap::decode(decoder)?))
+    }
+}
+
+impl Encode for AddCtx {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.dot.encode(encoder);
+        encode_prev(encoder, &self.dot, self.prev);
+        self.clock.encode(encoder);
+    }
+}
+
+impl Decode for AddCtx {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let dot = Dot::decode(decoder)?;
+        Ok(AddCtx {
+            dot,
+            prev: decode_prev(decoder, &dot)?,
+            clock: VClock::decode(decoder)?,
+        })
+    }
+}
+
+/// Writes the previous counter of the dot's actor as its distance to the dot,
+/// which stays small even for physical timestamps.
+pub(crate) fn encode_prev(encoder: &mut Encoder, dot: &Dot, prev: u64) {
+    encoder.write_u64(dot.counter.wrapping_sub(prev));
+}
+
+pub(crate) fn decode_prev(decoder: &mut Decoder<'_>, dot: &Dot) -> Result<u64, DecodeError> {
+    Ok(dot.counter.wrapping_sub(decoder.read_u64()?))
+}
+
+impl<T: CmRDT> Encode for Envelope<T>
+where
+    T::Op: Encode,
+{
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_u64(self.version.into());
+        self.op.encode(encoder);
+        self.ctx.encode(encoder);
+    }
+}
+
+impl<T: CmRDT> Decode for Envelope<T>
+where
+    T::Op: Decode,
+{
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        Ok(Envelope {
+            version: u32::decode(decoder)?,
+            op: T::Op::decode(decoder)?,
+            ctx: AddCtx::decode(decoder)?,
+        })
+    }
+}
+
+impl<T: CmRDT> Encode for Batch<T>
+where
+    T::Op: Encode,
+{
+    fn encode(&self, encoder: &mut Encoder) {
+        self.ops.encode(encoder);
+        self.ctx.encode(encoder);
+    }
+}
+
+impl<T: CmRDT> Decode for Batch<T>
+where
+    T::Op: Decode,
+{
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        Ok(Batch {
+            ops: Vec::decode(decoder)?,
+            ctx: AddCtx::decode(decoder)?,
+        })
+    }
+}
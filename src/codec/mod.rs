@@ -0,0 +1,217 @@
+//! A compact binary encoding for operations and their causal context.
+//!
+//! Unlike a generic serde format, which spells out every actor id of every
+//! `VClock` entry in full, this encoding writes integers as varints and refers
+//! to actors through a per-frame table. A frame is produced by [`encode`] and
+//! read back by [`decode`].
+//!
+//! # Byte layout
+//!
+//! Every frame starts with the version of the layout it was written with,
+//! currently [`CODEC_VERSION`]. The layout of a given version never changes:
+//! any change to it comes with a new version, and a frame of a version that
+//! this release does not know is rejected with
+//! [`DecodeError::UnsupportedVersion`] rather than misread. This page
+//! describes version 1.
+//!
+//! ```text
+//! frame      := version:u8 actor_count:varint actor_id:varint{actor_count} value
+//!
+//! varint     := unsigned LEB128, 7 bits per byte, least significant group first
+//! zigzag     := varint of (n << 1) ^ (n >> 63), for signed integers
+//! actor      := varint index into the frame's actor table
+//! len        := varint number of elements (or bytes, for strings)
+//!
+//! Dot        := actor counter:varint
+//! VClock     := len (actor counter:varint){len}      in ascending actor order
+//! AddCtx     := Dot since:varint VClock              since = counter - prev
+//! Envelope   := version:varint Op AddCtx
+//! Batch      := len Op{len} AddCtx
+//! Op         := tag:varint field*                    tag is the variant index
+//! ```
+//!
+//! Actors are added to the table in the order they are first written, so the
+//! table is usually tiny and an actor costs a single byte wherever it is used.
+//! Each variant of an operation is tagged with its index in the declaration of
+//! the `Op` enum, followed by its fields in order. Other values are written as
+//! follows:
+//!
+//! | Type                          | Encoding                                   |
+//! |-------------------------------|--------------------------------------------|
+//! | `u8`                          | one raw byte                               |
+//! | `u16`, `u32`, `u64`, `usize`  | varint                                     |
+//! | `i8` .. `i64`, `isize`        | zigzag                                     |
+//! | `bool`                        | one byte, `0` or `1`                       |
+//! | `char`                        | varint of the scalar value                 |
+//! | `String`                      | len, then the UTF-8 bytes                  |
+//! | `Vec`, `BTreeSet`             | len, then each element                     |
+//! | `BTreeMap`                    | len, then each key followed by its value   |
+//! | `Option`                      | `0` for `None`, or `1` then the value      |
+//! | tuples                        | each element in order                      |
+//! | `ActorId`                     | actor                                      |
+//!
+//! User-defined values, such as the elements of a set, implement [`Encode`]
+//! and [`Decode`] with the primitives of [`Encoder`] and [`Decoder`].
+//!
+//! # Sessions
+//!
+//! Over a connection that delivers messages in order, [`SessionEncoder`] and
+//! [`SessionDecoder`] send each context as a [`DeltaCtx`], holding only the
+//! clock entries that changed since the previous message:
+//!
+//! ```text
+//! DeltaCtx   := Dot since:varint full:bool VClock
+//! ```
+
+mod decoder;
+mod encoder;
+mod error;
+mod impls;
+mod ops;
+mod session;
+
+pub use decoder::Decoder;
+pub use encoder::Encoder;
+pub use error::DecodeError;
+pub use session::{DeltaCtx, SessionDecoder, SessionEncoder};
+
+/// The version of the byte layout written at the start of every frame.
+pub const CODEC_VERSION: u8 = 1;
+
+/// A value that can be written with the binary codec.
+pub trait Encode {
+    fn encode(&self, encoder: &mut Encoder);
+}
+
+/// A value that can be read with the binary codec.
+pub trait Decode: Sized {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError>;
+}
+
+/// Encodes a value into a self-contained frame.
+pub fn encode<V: Encode + ?Sized>(value: &V) -> Vec<u8> {
+    let mut encoder = Encoder::new();
+    value.encode(&mut encoder);
+    encoder.finish()
+}
+
+/// Decodes a value from a frame produced by [`encode`]. The frame must contain
+/// nothing after the value.
+pub fn decode<V: Decode>(frame: &[u8]) -> Result<V, DecodeError> {
+    let mut decoder = Decoder::new(frame)?;
+    let value = V::decode(&mut decoder)?;
+    match decoder.remaining() {
+        0 => Ok(value),
+        trailing => Err(DecodeError::TrailingBytes(trailing)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, AddCtx, Dot, Envelope, Replica, VClock};
+    use crate::or_set::{self, ORSet};
+
+    #[test]
+    fn test_varint_layout() {
+        assert_eq!(encode(&0u64), [1, 0, 0x00]);
+        assert_eq!(encode(&127u64), [1, 0, 0x7f]);
+        assert_eq!(encode(&300u64), [1, 0, 0xac, 0x02]);
+        assert_eq!(encode(&u64::MAX).len(), 2 + 10);
+        assert_eq!(encode(&-1i64), [1, 0, 0x01]);
+        assert_eq!(encode(&1i64), [1, 0, 0x02]);
+    }
+
+    #[test]
+    fn test_integers_roundtrip() {
+        for value in [0, 1, 127, 128, 300, u64::MAX >> 1, u64::MAX] {
+            assert_eq!(decode::<u64>(&encode(&value)), Ok(value));
+        }
+        for value in [0, 1, -1, 63, -64, i64::MIN, i64::MAX] {
+            assert_eq!(decode::<i64>(&encode(&value)), Ok(value));
+        }
+    }
+
+    #[test]
+    fn test_actors_are_written_once() {
+        // Arrange: a context that mentions actor 1000 twice.
+        let ctx = AddCtx {
+            dot: Dot {
+                actor: ActorId(1000),
+                counter: 3,
+            },
+            prev: 2,
+            clock: VClock([(ActorId(1000), 3), (ActorId(7), 2)].into()),
+        };
+
+        // Act
+        let frame = encode(&ctx);
+
+        // Assert: the table lists 1000 then 7, and the body uses their indices.
+        assert_eq!(frame, [1, 2, 0xe8, 0x07, 7, 0, 3, 1, 2, 1, 2, 0, 3]);
+        assert_eq!(decode::<AddCtx>(&frame), Ok(ctx));
+    }
+
+    #[test]
+    fn test_malformed_frames_are_rejected() {
+        let frame = encode(&or_set::Op::Add(String::from("x")));
+
+        assert_eq!(
+            decode::<or_set::Op<String>>(&frame[..frame.len() - 1]),
+            Err(DecodeError::UnexpectedEof)
+        );
+        assert_eq!(
+            decode::<or_set::Op<String>>(&[1, 0, 9, 0]),
+            Err(DecodeError::InvalidTag(9))
+        );
+        assert_eq!(
+            decode::<Dot>(&[1, 0, 0, 1]),
+            Err(DecodeError::UnknownActor(0))
+        );
+        assert_eq!(
+            decode::<u64>(&[
+                1, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02
+            ]),
+            Err(DecodeError::VarintOverflow)
+        );
+        assert_eq!(
+            decode::<u8>(&[1, 0, 1, 2]),
+            Err(DecodeError::TrailingBytes(1))
+        );
+        assert_eq!(decode::<u8>(&[]), Err(DecodeError::UnexpectedEof));
+    }
+
+    #[test]
+    fn test_unknown_versions_are_rejected() {
+        let mut frame = encode(&300u64);
+        assert_eq!(frame[0], CODEC_VERSION);
+
+        for version in [0, CODEC_VERSION + 1, 0xff] {
+            frame[0] = version;
+            assert_eq!(
+                decode::<u64>(&frame),
+                Err(DecodeError::UnsupportedVersion(version))
+            );
+        }
+    }
+
+    #[test]
+    fn test_envelope_is_smaller_than_json() {
+        // Arrange: an op sent after hearing from a few other replicas.
+        let mut replicas: Vec<_> = (1..=4)
+            .map(|id| Replica::new(ActorId(id << 40), ORSet::default()))
+            .collect();
+        for i in 1..4 {
+            let (op, ctx) = replicas[i].apply(or_set::Op::Add(i as u32));
+            replicas[0].apply_remote(op, ctx);
+        }
+        let envelope = Envelope::<ORSet<u32>>::from(replicas[0].apply(or_set::Op::Rm(2)));
+
+        // Act
+        let frame = encode(&envelope);
+
+        // Assert
+        assert!(frame.len() * 3 < serde_json::to_vec(&envelope).unwrap().len());
+        assert_eq!(decode::<Envelope<ORSet<u32>>>(&frame), Ok(envelope));
+    }
+}
@@ -0,0 +1,66 @@
+use crate::codec::{Decode, DecodeError, Decoder, Encode, Encoder};
+use crate::{
+    bounded_counter, flag, g_counter, g_set, lattice_register, lww_element_set, lww_map,
+    lww_register, max_register, min_register, mv_register, or_map, or_set, pn_counter,
+    reset_counter, rga, two_p_set,
+};
+
+/// Implements the codec for an `Op` enum: the tag of each variant, followed by
+/// its fields in order. Tags must never be reused or renumbered.
+macro_rules! op_codec {
+    (
+        [$($param:ident),*] $op:ty {
+            $($tag:literal => $variant:ident $(($($field:ident),+))?),+ $(,)?
+        }
+    ) => {
+        impl<$($param: Encode),*> Encode for $op {
+            fn encode(&self, encoder: &mut Encoder) {
+                match self {
+                    $(Self::$variant $(($($field),+))? => {
+                        encoder.write_u64($tag);
+                        $($($field.encode(encoder);)+)?
+                    })+
+                }
+            }
+        }
+
+        impl<$($param: Decode),*> Decode for $op {
+            fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                match decoder.read_u64()? {
+                    $($tag => Ok(Self::$variant $(($({
+                        let $field = Decode::decode(decoder)?;
+                        $field
+                    }),+))?),)+
+                    tag => Err(DecodeError::InvalidTag(tag)),
+                }
+            }
+        }
+    };
+}
+
+op_codec!([] g_counter::Op { 0 => Inc(amount) });
+op_codec!([] pn_counter::Op { 0 => Inc(amount), 1 => Dec(amount) });
+op_codec!([] reset_counter::Op {
+    0 => Inc(amount),
+    1 => Dec(amount),
+    2 => Reset,
+});
+op_codec!([] bounded_counter::Op {
+    0 => Inc(amount),
+    1 => Dec(amount),
+    2 => Transfer(to, amount),
+});
+// Shared by `ew_flag` and `dw_flag`.
+op_codec!([] flag::Op { 0 => Enable, 1 => Disable });
+op_codec!([T] lww_register::Op<T> { 0 => Set(value) });
+op_codec!([T] mv_register::Op<T> { 0 => Set(value) });
+op_codec!([T] max_register::Op<T> { 0 => Set(value) });
+op_codec!([T] min_register::Op<T> { 0 => Set(value) });
+op_codec!([L] lattice_register::Op<L> { 0 => Join(value) });
+op_codec!([K, V] lww_map::Op<K, V> { 0 => Set(key, value), 1 => Remove(key) });
+op_codec!([T] g_set::Op<T> { 0 => Add(value) });
+op_codec!([T] two_p_set::Op<T> { 0 => Add(value), 1 => Rm(value) });
+op_codec!([T] lww_element_set::Op<T> { 0 => Add(value), 1 => Rm(value) });
+op_codec!([T] or_set::Op<T> { 0 => Add(value), 1 => Rm(value) });
+op_codec!([K, O] or_map::Op<K, O> { 0 => Update(key, op), 1 => Rm(key) });
+op_codec!([T] rga::Op<T> { 0 => InsertAfter(parent, value), 1 => Delete(id) });
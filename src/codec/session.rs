@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+
+use crate::codec::impls::{decode_prev, encode_prev};
+use crate::codec::{self, Decode, DecodeError, Decoder, Encode, Encoder};
+use crate::core::{AddCtx, CmRDT, Dot, VClock};
+
+/// An [`AddCtx`] whose clock is sent relative to the clock of the previous
+/// context sent in the same session.
+///
+/// The receiver starts from the previous clock, sets the entry of `dot` to its
+/// counter, and then applies `changes`: each entry replaces the counter of its
+/// actor, and an entry of zero removes the actor. When `full` is set, `changes`
+/// is the whole clock instead, which starts a new session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaCtx {
+    pub dot: Dot,
+    pub prev: u64,
+    pub full: bool,
+    pub changes: VClock,
+}
+
+/// Compresses the contexts sent to a single peer.
+///
+/// Since a replica's clock only grows, consecutive contexts usually differ in
+/// a handful of entries, and often only in the entry of the op's own dot,
+/// which is implied. The peer's [`SessionDecoder`] must receive every
+/// [`DeltaCtx`] in the order they were produced. After a reconnect, or any
+/// message that may have been lost, both sides call `reset` and the next
+/// context is sent in full.
+#[derive(Debug, Clone, Default)]
+pub struct SessionEncoder {
+    last: Option<VClock>,
+}
+
+impl SessionEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets the previous clock, so that the next context is sent in full.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Returns the context to send, relative to the previous one.
+    pub fn encode(&mut self, ctx: &AddCtx) -> DeltaCtx {
+        let Some(mut base) = self.last.replace(ctx.clock.clone()) else {
+            return DeltaCtx {
+                dot: ctx.dot,
+                prev: ctx.prev,
+                full: true,
+                changes: ctx.clock.clone(),
+            };
+        };
+
+        base.0.insert(ctx.dot.actor, ctx.dot.counter);
+        let mut changes = VClock::default();
+        for (actor, counter) in &ctx.clock.0 {
+            if base.0.remove(actor) != Some(*counter) {
+                changes.0.insert(*actor, *counter);
+            }
+        }
+        // Whatever is left in the base is not in the new clock.
+        for actor in base.0.into_keys() {
+            changes.0.insert(actor, 0);
+        }
+
+        DeltaCtx {
+            dot: ctx.dot,
+            prev: ctx.prev,
+            full: false,
+            changes,
+        }
+    }
+
+    /// Encodes an operation and its compressed context into a binary frame.
+    pub fn encode_frame<T: CmRDT>(&mut self, op: &T::Op, ctx: &AddCtx) -> Vec<u8>
+    where
+        T::Op: Encode,
+    {
+        codec::encode(&(op, self.encode(ctx)))
+    }
+}
+
+/// Rebuilds the full contexts sent by a peer's [`SessionEncoder`].
+#[derive(Debug, Clone, Default)]
+pub struct SessionDecoder {
+    last: Option<VClock>,
+}
+
+impl SessionDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets the previous clock, so that only a full context is accepted next.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Rebuilds the full context, ready for `Replica::apply_remote`.
+    pub fn decode(&mut self, delta: DeltaCtx) -> Result<AddCtx, DecodeError> {
+        let clock = if delta.full {
+            delta.changes
+        } else {
+            let mut clock = self.last.take().ok_or(DecodeError::MissingBaseline)?;
+            clock.0.insert(delta.dot.actor, delta.dot.counter);
+            for (actor, counter) in delta.changes.0 {
+                if counter == 0 {
+                    clock.0.remove(&actor);
+                } else {
+                    clock.0.insert(actor, counter);
+                }
+            }
+            clock
+        };
+
+        self.last = Some(clock.clone());
+        Ok(AddCtx {
+            dot: delta.dot,
+            prev: delta.prev,
+            clock,
+        })
+    }
+
+    /// Decodes a frame produced by [`SessionEncoder::encode_frame`].
+    pub fn decode_frame<T: CmRDT>(&mut self, frame: &[u8]) -> Result<(T::Op, AddCtx), DecodeError>
+    where
+        T::Op: Decode,
+    {
+        let (op, delta) = codec::decode::<(T::Op, DeltaCtx)>(frame)?;
+        Ok((op, self.decode(delta)?))
+    }
+}
+
+impl Encode for DeltaCtx {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.dot.encode(encoder);
+        encode_prev(encoder, &self.dot, self.prev);
+        self.full.encode(encoder);
+        self.changes.encode(encoder);
+    }
+}
+
+impl Decode for DeltaCtx {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let dot = Dot::decode(decoder)?;
+        Ok(DeltaCtx {
+            dot,
+            prev: decode_prev(decoder, &dot)?,
+            full: bool::decode(decoder)?,
+            changes: VClock::decode(decoder)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+    use crate::or_set::{self, ORSet};
+
+    /// A replica that has heard from ten others.
+    fn busy_replica() -> Replica<ORSet<u32>> {
+        let mut replica = Replica::new(ActorId(1), ORSet::default());
+        for id in 2..12 {
+            let mut other = Replica::new(ActorId(id), ORSet::default());
+            let (op, ctx) = other.apply(or_set::Op::Add(id as u32));
+            replica.apply_remote(op, ctx);
+        }
+        replica
+    }
+
+    #[test]
+    fn test_only_changed_entries_are_sent() {
+        // Arrange
+        let mut replica = busy_replica();
+        let mut encoder = SessionEncoder::new();
+        let mut decoder = SessionDecoder::new();
+        let (_, first) = replica.apply(or_set::Op::Add(100));
+        let (_, second) = replica.apply(or_set::Op::Add(101));
+
+        // Act
+        let first_delta = encoder.encode(&first);
+        let second_delta = encoder.encode(&second);
+
+        // Assert: the second clock only moved the op's own entry.
+        assert!(first_delta.full);
+        assert_eq!(first_delta.changes.0.len(), 11);
+        assert!(!second_delta.full);
+        assert!(second_delta.changes.0.is_empty());
+        assert_eq!(decoder.decode(first_delta), Ok(first));
+        assert_eq!(decoder.decode(second_delta), Ok(second));
+    }
+
+    #[test]
+    fn test_older_and_smaller_clocks_are_rebuilt() {
+        // Arrange: an op is forwarded after a newer one from a busier replica.
+        let mut replica = busy_replica();
+        let (_, newer) = replica.apply(or_set::Op::Add(1));
+        let mut other = Replica::new(ActorId(20), ORSet::default());
+        let (_, older) = other.apply(or_set::Op::Add(2));
+        let mut encoder = SessionEncoder::new();
+        let mut decoder = SessionDecoder::new();
+
+        // Act & Assert
+        for ctx in [newer.clone(), older, newer] {
+            let delta = encoder.encode(&ctx);
+            assert_eq!(decoder.decode(delta), Ok(ctx));
+        }
+    }
+
+    #[test]
+    fn test_reset_falls_back_to_full_clock() {
+        // Arrange: the connection dropped after the first op.
+        let mut replica = busy_replica();
+        let mut encoder = SessionEncoder::new();
+        let mut decoder = SessionDecoder::new();
+        let (_, first) = replica.apply(or_set::Op::Add(1));
+        decoder.decode(encoder.encode(&first)).unwrap();
+        let (_, lost) = replica.apply(or_set::Op::Add(2));
+        encoder.encode(&lost);
+        let (_, after) = replica.apply(or_set::Op::Add(3));
+
+        // Act: the peer reconnects and only resets its decoder.
+        decoder.reset();
+        let rejected = decoder.decode(encoder.encode(&after));
+        encoder.reset();
+        let delta = encoder.encode(&after);
+
+        // Assert
+        assert_eq!(rejected, Err(DecodeError::MissingBaseline));
+        assert!(delta.full);
+        assert_eq!(decoder.decode(delta), Ok(after));
+    }
+
+    #[test]
+    fn test_frames_converge_replicas() {
+        // Arrange
+        let mut sender = busy_replica();
+        let mut receiver = Replica::new(ActorId(50), ORSet::default());
+        let mut encoder = SessionEncoder::new();
+        let mut decoder = SessionDecoder::new();
+        let mut sizes = Vec::new();
+
+        // Act
+        for value in 0..5 {
+            let (op, ctx) = sender.apply(or_set::Op::Add(value));
+            let frame = encoder.encode_frame::<ORSet<u32>>(&op, &ctx);
+            sizes.push(frame.len());
+            let (op, ctx) = decoder.decode_frame::<ORSet<u32>>(&frame).unwrap();
+            receiver.apply_remote(op, ctx);
+        }
+
+        // Assert
+        assert_eq!(receiver.read(), (0..5).collect());
+        assert_eq!(receiver.clock(), sender.clock());
+        assert!(sizes[1..].iter().all(|size| *size * 4 < sizes[0]));
+    }
+}
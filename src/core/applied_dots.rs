@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::core::{ActorId, Dot, VClock};
+
+/// A compact record of the dots that have been applied, stored as a version
+/// vector plus a set of exceptions.
+///
+/// The `clock` holds the highest counter applied from each actor, and every dot
+/// at or below it counts as applied unless it falls inside one of that actor's
+/// exception ranges. An exception range is opened whenever a dot arrives ahead
+/// of the counters below it, and is split or closed as those counters arrive.
+///
+/// Counters may skip values, so the counters an actor never used are recorded
+/// along with each dot through [`AppliedDots::insert_after`], from the `prev`
+/// counter of its context. This keeps the record down to the clock alone once
+/// every dot has arrived, however sparse the counters are.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct AppliedDots {
+    /// The highest counter applied from each actor.
+    pub clock: VClock,
+    /// For each actor, the inclusive ranges of counters below its `clock`
+    /// entry that have not been applied, keyed by their first counter.
+    pub exceptions: BTreeMap<ActorId, BTreeMap<u64, u64>>,
+}
+
+impl From<VClock> for AppliedDots {
+    /// Takes every dot the clock covers as applied.
+    fn from(clock: VClock) -> Self {
+        Self {
+            clock,
+            exceptions: BTreeMap::new(),
+        }
+    }
+}
+
+impl AppliedDots {
+    /// Returns `true` if the given dot has been applied.
+    pub fn contains(&self, dot: &Dot) -> bool {
+        if dot.counter == 0 || !self.clock.contains(dot) {
+            return false;
+        }
+
+        !self.exceptions.get(&dot.actor).is_some_and(|ranges| {
+            ranges
+                .range(..=dot.counter)
+                .next_back()
+                .is_some_and(|(_, end)| *end >= dot.counter)
+        })
+    }
+
+    /// Returns `true` if the given dot and every earlier dot from the same actor
+    /// have been applied, or skipped by that actor.
+    pub fn contains_up_to(&self, dot: &Dot) -> bool {
+        self.clock.contains(dot)
+            && !self.exceptions.get(&dot.actor).is_some_and(|ranges| {
+                ranges
+                    .first_key_value()
+                    .is_some_and(|(start, _)| *start <= dot.counter)
+            })
+    }
+
+    /// Returns `true` if every dot covered by the given clock has been applied,
+    /// or skipped by its actor.
+    pub fn covers(&self, clock: &VClock) -> bool {
+        clock.0.iter().all(|(actor, counter)| {
+            self.contains_up_to(&Dot {
+                actor: *actor,
+                counter: *counter,
+            })
+        })
+    }
+
+    /// Returns the greatest clock that these applied dots cover: for each
+    /// actor, the counter below its first exception, or its `clock` entry.
+    pub fn frontier(&self) -> VClock {
+        let mut frontier = self.clock.clone();
+        for (actor, ranges) in &self.exceptions {
+            if let Some((start, _)) = ranges.first_key_value() {
+                frontier.0.insert(*actor, start - 1);
+            }
+        }
+        frontier
+    }
+
+    /// Records a single dot as applied, without assuming anything about the
+    /// counters below it.
+    pub fn insert(&mut self, dot: Dot) {
+        self.insert_range(dot.actor, dot.counter, dot.counter);
+    }
+
+    /// Records a dot as applied, along with the counters between it and `prev`,
+    /// the counter of the previous dot of the same actor, which that actor
+    /// skipped.
+    pub fn insert_after(&mut self, dot: Dot, prev: u64) {
+        self.insert_range(dot.actor, prev + 1, dot.counter);
+    }
+
+    /// Records the given dot and every earlier dot from the same actor as applied.
+    pub fn insert_up_to(&mut self, dot: Dot) {
+        self.insert_range(dot.actor, 1, dot.counter);
+    }
+
+    /// Records every dot covered by the given clock as applied.
+    pub fn cover(&mut self, clock: &VClock) {
+        for (actor, counter) in &clock.0 {
+            self.insert_up_to(Dot {
+                actor: *actor,
+                counter: *counter,
+            });
+        }
+    }
+
+    /// Records the counters from `first` to `last`, inclusive, as applied.
+    fn insert_range(&mut self, actor: ActorId, first: u64, last: u64) {
+        let latest = self.clock.get(&actor);
+
+        if let Some(ranges) = self.exceptions.get_mut(&actor) {
+            // Cut the inserted counters out of every range they overlap,
+            // keeping the parts of each range on either side of them.
+            let overlapping: Vec<(u64, u64)> = ranges
+                .range(..=last)
+                .rev()
+                .take_while(|(_, end)| **end >= first)
+                .map(|(start, end)| (*start, *end))
+                .collect();
+            for (start, end) in overlapping {
+                ranges.remove(&start);
+                if start < first {
+                    ranges.insert(start, first - 1);
+                }
+                if end > last {
+                    ranges.insert(last + 1, end);
+                }
+            }
+            if ranges.is_empty() {
+                self.exceptions.remove(&actor);
+            }
+        }
+
+        if last > latest {
+            if first > latest + 1 {
+                self.exceptions
+                    .entry(actor)
+                    .or_default()
+                    .insert(latest + 1, first - 1);
+            }
+            self.clock.0.insert(actor, last);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dot(actor: u64, counter: u64) -> Dot {
+        Dot {
+            actor: ActorId(actor),
+            counter,
+        }
+    }
+
+    #[test]
+    fn test_contiguous_dots_leave_no_exceptions() {
+        let mut applied = AppliedDots::default();
+        for counter in 1..=3 {
+            applied.insert(dot(1, counter));
+        }
+
+        assert!(applied.contains(&dot(1, 2)));
+        assert!(!applied.contains(&dot(1, 4)));
+        assert!(applied.exceptions.is_empty());
+    }
+
+    #[test]
+    fn test_out_of_order_dots_open_and_fill_exceptions() {
+        let mut applied = AppliedDots::default();
+
+        applied.insert(dot(1, 5));
+        assert!(applied.contains(&dot(1, 5)));
+        assert!(!applied.contains(&dot(1, 3)));
+
+        applied.insert(dot(1, 3));
+        assert!(applied.contains(&dot(1, 3)));
+        assert!(!applied.contains(&dot(1, 2)));
+        assert!(!applied.contains(&dot(1, 4)));
+        assert_eq!(applied.exceptions[&ActorId(1)].len(), 2);
+
+        applied.insert(dot(1, 1));
+        applied.insert(dot(1, 2));
+        applied.insert(dot(1, 4));
+        assert!(applied.exceptions.is_empty());
+    }
+
+    #[test]
+    fn test_cover_closes_exceptions() {
+        let mut applied = AppliedDots::default();
+        applied.insert(dot(1, 2));
+        applied.insert(dot(1, 9));
+
+        let mut clock = VClock::default();
+        clock.0.insert(ActorId(1), 5);
+        applied.cover(&clock);
+
+        assert!(applied.contains(&dot(1, 1)));
+        assert!(applied.contains(&dot(1, 5)));
+        assert!(!applied.contains(&dot(1, 6)));
+        assert!(applied.contains(&dot(1, 9)));
+        assert_eq!(applied.exceptions[&ActorId(1)].len(), 1);
+        assert!(applied.covers(&clock));
+        assert_eq!(applied.frontier(), clock);
+        clock.0.insert(ActorId(1), 9);
+        assert!(!applied.covers(&clock));
+    }
+
+    #[test]
+    fn test_skipped_counters_leave_no_exceptions() {
+        let mut applied = AppliedDots::default();
+
+        // The actor used counters 2, 5 and 9, which arrive out of order.
+        applied.insert_after(dot(1, 9), 5);
+        applied.insert_after(dot(1, 2), 0);
+        assert!(!applied.contains(&dot(1, 5)));
+        assert_eq!(applied.exceptions[&ActorId(1)].len(), 1);
+
+        applied.insert_after(dot(1, 5), 2);
+        assert!(applied.contains(&dot(1, 5)));
+        assert!(applied.exceptions.is_empty());
+    }
+}
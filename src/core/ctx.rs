@@ -1,14 +1,49 @@
 use serde::{Deserialize, Serialize};
 
+use crate::Error;
 use crate::core::{Dot, VClock};
 
 /// Context required for applying a new operation (the "add" context).
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AddCtx {
     pub dot: Dot,
+    /// The counter of the previous operation generated by the same actor, or
+    /// zero if this is its first one. Counters may skip values, so this is the
+    /// only way to tell which operation of its own actor an operation follows.
+    pub prev: u64,
     pub clock: VClock,
 }
 
+impl AddCtx {
+    /// Checks that the context is one a `Replica` could have generated: the dot
+    /// must have a non-zero counter, follow its previous counter, and be
+    /// covered by its own clock.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.dot.counter == 0 {
+            return Err(Error::ZeroCounter(self.dot));
+        }
+        if self.prev >= self.dot.counter {
+            return Err(Error::InvalidPrevious(self.dot));
+        }
+        if !self.clock.contains(&self.dot) {
+            return Err(Error::DotNotInClock(self.dot));
+        }
+        Ok(())
+    }
+
+    /// Returns the clock of the operations this one observed, which is its
+    /// clock without its own dot or any counter its actor skipped before it.
+    pub fn observed(&self) -> VClock {
+        let mut clock = self.clock.clone();
+        if self.prev == 0 {
+            clock.0.remove(&self.dot.actor);
+        } else {
+            clock.0.insert(self.dot.actor, self.prev);
+        }
+        clock
+    }
+}
+
 /// Context for reading a value (could be just the causal context).
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ReadCtx {
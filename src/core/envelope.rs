@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::{AddCtx, CmRDT};
+
+/// The current version of the [`Envelope`] wire format.
+///
+/// Version 2 added `AddCtx::prev`, without which a receiver cannot tell which
+/// counters the sender skipped, so version 1 envelopes are no longer accepted.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// A single operation bundled with its causal context, ready to be put on the wire.
+///
+/// The `version` records the wire format the envelope was produced with, so that
+/// a receiver can detect envelopes from incompatible releases before applying them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T::Op: Serialize",
+    deserialize = "T::Op: Deserialize<'de>"
+))]
+pub struct Envelope<T: CmRDT> {
+    pub version: u32,
+    pub op: T::Op,
+    pub ctx: AddCtx,
+}
+
+impl<T: CmRDT> Envelope<T> {
+    /// Creates an envelope using the current format version.
+    pub fn new(op: T::Op, ctx: AddCtx) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            op,
+            ctx,
+        }
+    }
+
+    /// Splits the envelope into the operation and its context, as expected by
+    /// `Replica::apply_remote`.
+    pub fn into_parts(self) -> (T::Op, AddCtx) {
+        (self.op, self.ctx)
+    }
+}
+
+impl<T: CmRDT> From<(T::Op, AddCtx)> for Envelope<T> {
+    fn from((op, ctx): (T::Op, AddCtx)) -> Self {
+        Self::new(op, ctx)
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::Error;
+use crate::core::{AddCtx, Dot};
+
+/// The number of low bits of a physical timestamp used for the logical counter.
+pub const LOGICAL_BITS: u32 = 16;
+
+/// A source of physical time, in milliseconds since the Unix epoch.
+pub trait PhysicalClock: Debug + Send + Sync {
+    fn now_millis(&self) -> u64;
+}
+
+/// The system wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl PhysicalClock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0)
+    }
+}
+
+/// A Hybrid Logical Clock backed by a physical clock source.
+///
+/// A timestamp packs the physical milliseconds into the high bits of a `u64`
+/// and a logical counter into the low [`LOGICAL_BITS`], so comparing two
+/// timestamps compares their physical time first. It is used as the counter of
+/// a [`Dot`], which keeps every other part of the crate unchanged.
+#[derive(Debug, Clone)]
+pub struct Hlc {
+    source: Arc<dyn PhysicalClock>,
+    max_drift_ms: u64,
+}
+
+impl Hlc {
+    /// Creates a clock that rejects timestamps more than `max_drift_ms` ahead
+    /// of the source.
+    pub fn new(source: impl PhysicalClock + 'static, max_drift_ms: u64) -> Self {
+        Self {
+            source: Arc::new(source),
+            max_drift_ms,
+        }
+    }
+
+    /// Returns the physical milliseconds of a timestamp.
+    pub fn physical_ms(timestamp: u64) -> u64 {
+        timestamp >> LOGICAL_BITS
+    }
+
+    /// Returns a timestamp that is at least the current physical time and
+    /// strictly greater than `latest`, the greatest timestamp observed so far.
+    pub fn next(&self, latest: u64) -> u64 {
+        let physical = self.source.now_millis() << LOGICAL_BITS;
+        physical.max(latest + 1)
+    }
+
+    /// Rejects a dot whose timestamp is further ahead of the physical clock
+    /// than the allowed drift.
+    pub fn check(&self, dot: &Dot) -> Result<(), Error> {
+        let limit = self.source.now_millis().saturating_add(self.max_drift_ms);
+        if Self::physical_ms(dot.counter) > limit {
+            return Err(Error::ClockDrift(*dot));
+        }
+        Ok(())
+    }
+
+    /// Rejects a context whose dot, or any event its clock references, is
+    /// further ahead of the physical clock than the allowed drift.
+    ///
+    /// Every entry of the clock is merged into the receiver's clock and raises
+    /// its later timestamps, so an entry of any actor must be within bounds.
+    pub fn check_ctx(&self, ctx: &AddCtx) -> Result<(), Error> {
+        self.check(&ctx.dot)?;
+        for (&actor, &counter) in &ctx.clock.0 {
+            self.check(&Dot { actor, counter })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, VClock};
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[derive(Debug, Clone, Default)]
+    struct FakeClock(Arc<AtomicU64>);
+
+    impl PhysicalClock for FakeClock {
+        fn now_millis(&self) -> u64 {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+
+    #[test]
+    fn test_next_follows_physical_time() {
+        let time = FakeClock::default();
+        let hlc = Hlc::new(time.clone(), 0);
+        time.0.store(5, Ordering::SeqCst);
+
+        let first = hlc.next(0);
+        let second = hlc.next(first);
+        time.0.store(6, Ordering::SeqCst);
+        let third = hlc.next(second);
+
+        assert_eq!(first, 5 << LOGICAL_BITS);
+        assert_eq!(second, (5 << LOGICAL_BITS) + 1);
+        assert_eq!(third, 6 << LOGICAL_BITS);
+    }
+
+    #[test]
+    fn test_next_never_goes_backwards() {
+        let time = FakeClock::default();
+        let hlc = Hlc::new(time.clone(), 0);
+        time.0.store(10, Ordering::SeqCst);
+        let ahead = hlc.next(0);
+
+        time.0.store(3, Ordering::SeqCst);
+        assert_eq!(hlc.next(ahead), ahead + 1);
+    }
+
+    #[test]
+    fn test_check_rejects_excessive_drift() {
+        let time = FakeClock::default();
+        let hlc = Hlc::new(time.clone(), 100);
+        time.0.store(1_000, Ordering::SeqCst);
+        let dot = |ms: u64| Dot {
+            actor: ActorId(1),
+            counter: ms << LOGICAL_BITS,
+        };
+
+        assert_eq!(hlc.check(&dot(1_100)), Ok(()));
+        assert_eq!(hlc.check(&dot(1_101)), Err(Error::ClockDrift(dot(1_101))));
+    }
+
+    #[test]
+    fn test_check_ctx_rejects_drift_of_any_clock_entry() {
+        let time = FakeClock::default();
+        let hlc = Hlc::new(time.clone(), 100);
+        time.0.store(1_000, Ordering::SeqCst);
+        let dot = Dot {
+            actor: ActorId(1),
+            counter: 1_000 << LOGICAL_BITS,
+        };
+        let poisoned = Dot {
+            actor: ActorId(3),
+            counter: 5_000 << LOGICAL_BITS,
+        };
+        let ctx = AddCtx {
+            dot,
+            prev: 0,
+            clock: VClock(BTreeMap::from([
+                (dot.actor, dot.counter),
+                (poisoned.actor, poisoned.counter),
+            ])),
+        };
+
+        assert_eq!(hlc.check_ctx(&ctx), Err(Error::ClockDrift(poisoned)));
+    }
+}
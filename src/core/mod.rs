@@ -1,14 +1,25 @@
 mod actor;
+mod applied_dots;
 mod ctx;
 mod dot;
+mod envelope;
+mod hlc;
 mod replica;
+pub(crate) mod serde_pairs;
+mod snapshot;
 mod traits;
+mod transaction;
 mod vclock;
 
 // Public API
 pub use actor::ActorId;
+pub use applied_dots::AppliedDots;
 pub use ctx::{AddCtx, ReadCtx};
 pub use dot::Dot;
-pub use replica::Replica;
-pub use traits::CmRDT;
+pub use envelope::{Envelope, FORMAT_VERSION};
+pub use hlc::{Hlc, LOGICAL_BITS, PhysicalClock, SystemClock};
+pub use replica::{ApplyOutcome, Replica};
+pub use snapshot::Snapshot;
+pub use traits::{CmRDT, DeltaCmRDT, InfallibleLocal, Lattice};
+pub use transaction::{Batch, Ops, Transaction};
 pub use vclock::VClock;
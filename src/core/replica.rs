@@ -1,4 +1,21 @@
-use crate::core::{ActorId, AddCtx, CmRDT, Dot, VClock};
+use std::collections::BTreeMap;
+
+use crate::Error;
+use crate::core::{
+    ActorId, AddCtx, AppliedDots, Batch, CmRDT, DeltaCmRDT, Dot, Hlc, InfallibleLocal,
+    PhysicalClock, Snapshot, Transaction, VClock,
+};
+
+/// The result of delivering a remote operation to a [`Replica`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyOutcome {
+    /// The operation was applied to the CRDT.
+    Applied,
+    /// The operation had already been applied, so it was skipped.
+    Duplicate,
+    /// The operation is waiting for its causal predecessors (causal delivery only).
+    Buffered,
+}
 
 /// A Replica manages the state for a single actor, providing the core CRDT functionality.
 ///
@@ -19,12 +36,66 @@ use crate::core::{ActorId, AddCtx, CmRDT, Dot, VClock};
 ///
 /// This mechanism provides intuitive behavior for all CRDTs without relying on
 /// synchronized physical clocks.
+///
+/// A replica created with [`Replica::with_physical_clock`] instead uses an
+/// [`Hlc`] backed by a physical clock: the counter is the current time in
+/// milliseconds, shifted left by [`LOGICAL_BITS`](crate::core::LOGICAL_BITS),
+/// whenever that is greater than everything observed, so Last-Write-Wins types
+/// follow real write order. [`Replica::try_apply_remote`] then also rejects
+/// operations timestamped too far in the future. Every replica of a CRDT
+/// should use the same mode, as physical timestamps always exceed logical ones.
+///
+/// ## Causal Delivery
+/// By default, remote operations are applied as soon as they arrive. A replica
+/// created with [`Replica::with_causal_delivery`] instead buffers any operation
+/// whose clock references events that have not been applied yet, and applies it
+/// once they have.
+///
+/// Because the HLC may skip counter values, an operation's own actor entry in
+/// its clock says nothing about which of that actor's operations came before it.
+/// Every context therefore also carries `prev`, the counter of the previous
+/// operation of the same actor, and an operation waits for that one, as well as
+/// for every event it references from other actors.
+///
+/// ## Duplicate Detection
+/// Every replica records the dots it has applied in an [`AppliedDots`], so an
+/// operation that is delivered more than once only reaches `CmRDT::apply` the
+/// first time. A merged clock may cover events the merged state does not
+/// include, so state merged through [`Replica::merge`] is not recorded as
+/// applied. Redelivered operations that are already part of a merged state
+/// still rely on `CmRDT::apply` being idempotent, as it is for every type in
+/// this crate.
+///
+/// ## Causal Stability
+/// A replica remembers the latest clock it knows each peer to have, learned from
+/// the context of every remote operation it applies or reported explicitly with
+/// [`Replica::observe_peer`]. The pointwise minimum of these clocks and its own,
+/// returned by [`Replica::stable_clock`], covers only events that every replica
+/// has observed. [`Replica::purge`] passes it to `CmRDT::purge` so the CRDT can
+/// discard metadata kept for events that could still arrive concurrently.
+///
+/// The frontier is only as complete as the set of peers: every member must be
+/// registered with [`Replica::add_peer`] before purging, and operations must be
+/// delivered in causal order, so that a peer's clock also accounts for every
+/// operation it sent before.
+///
+/// ## Persistence
+/// [`Replica::snapshot`] captures the state, clocks and buffered operations of
+/// a replica, and [`Replica::restore`] loads them back into a freshly
+/// configured one. Operations applied after the snapshot are recovered with
+/// [`Replica::replay`], which also restores this replica's own operations with
+/// the dots they were originally given, so that no dot is ever handed out twice.
 #[derive(Debug, Clone)]
 pub struct Replica<T: CmRDT> {
     pub actor_id: ActorId,
     op_counter: u64,
     clock: VClock,
     crdt: T,
+    applied: AppliedDots,
+    causal_delivery: bool,
+    pending: BTreeMap<Dot, (T::Op, AddCtx)>,
+    peers: BTreeMap<ActorId, VClock>,
+    hlc: Option<Hlc>,
 }
 
 impl<T: CmRDT> Replica<T> {
@@ -35,47 +106,244 @@ impl<T: CmRDT> Replica<T> {
             op_counter: 0,
             clock: VClock::default(),
             crdt,
+            applied: AppliedDots::default(),
+            causal_delivery: false,
+            pending: BTreeMap::new(),
+            peers: BTreeMap::new(),
+            hlc: None,
         }
     }
 
-    /// Applies an operation locally and returns the operation and its generated
-    /// context, ready to be sent over the network.
-    pub fn apply(&mut self, op: T::Op) -> (T::Op, AddCtx) {
-        // 1. Find the latest "time" (counter) this replica has seen from any actor.
-        let latest_known_time = self.clock.max_counter();
+    /// Enables causal delivery, so remote operations are only applied once all of
+    /// their causal predecessors have been.
+    pub fn with_causal_delivery(mut self) -> Self {
+        self.causal_delivery = true;
+        self
+    }
 
-        // 2. Ensure our new op's counter is causally newer than both our last op
-        //    and any other op we've seen.
-        self.op_counter = latest_known_time.max(self.op_counter) + 1;
+    /// Timestamps local operations with a Hybrid Logical Clock backed by the
+    /// given physical clock, and rejects remote operations that are more than
+    /// `max_drift_ms` ahead of it.
+    pub fn with_physical_clock(
+        mut self,
+        source: impl PhysicalClock + 'static,
+        max_drift_ms: u64,
+    ) -> Self {
+        self.hlc = Some(Hlc::new(source, max_drift_ms));
+        self
+    }
 
+    /// Checks that this replica may generate the operation, and applies it like
+    /// [`Replica::apply`] if so.
+    ///
+    /// This is the only way to apply a local operation to a CRDT that may refuse
+    /// it, such as a `BoundedCounter` decrement without enough rights. Nothing
+    /// is applied and no dot is consumed when an error is returned.
+    pub fn try_apply(&mut self, op: T::Op) -> Result<(T::Op, AddCtx), Error> {
+        self.crdt.validate_local(&op, self.actor_id)?;
+        Ok(self.generate(op))
+    }
+
+    /// Assigns the next dot to an operation that passed `validate_local`, and
+    /// applies it.
+    fn generate(&mut self, op: T::Op) -> (T::Op, AddCtx) {
+        let ctx = self.next_ctx();
+        self.commit(op.clone(), ctx.clone());
+        (op, ctx)
+    }
+
+    /// Returns the context the next local operation will be generated with,
+    /// without consuming its dot.
+    pub(crate) fn next_ctx(&self) -> AddCtx {
         let dot = Dot {
             actor: self.actor_id,
-            counter: self.op_counter,
+            counter: self.next_counter(),
         };
 
-        // Update our own clock with our new operation
-        self.clock.0.insert(dot.actor, dot.counter);
-
-        // The context now contains our replica's full, updated clock state
-        let ctx = AddCtx {
+        // The context contains our replica's full clock, updated with the new
+        // operation.
+        let mut clock = self.clock.clone();
+        clock.0.insert(dot.actor, dot.counter);
+        AddCtx {
             dot,
-            clock: self.clock.clone(),
-        };
+            prev: self.op_counter,
+            clock,
+        }
+    }
+
+    /// Applies a local operation with the context returned by
+    /// [`Replica::next_ctx`].
+    pub(crate) fn commit(&mut self, op: T::Op, ctx: AddCtx) {
+        // Update our own clock with our new operation. Every op this replica
+        // has generated was applied locally, so its own history has no gaps.
+        self.op_counter = ctx.dot.counter;
+        self.clock.0.insert(ctx.dot.actor, ctx.dot.counter);
+        self.applied.insert_up_to(ctx.dot);
 
         // Apply the op to the local CRDT state
-        self.crdt.apply(op.clone(), ctx.clone());
+        self.crdt.apply(op, ctx);
+    }
 
-        // Return the op and context to the caller
-        (op, ctx)
+    /// Checks that this replica may generate the operation, and returns the
+    /// context it would be generated with, like [`Replica::try_apply`] without
+    /// applying it.
+    pub(crate) fn prepare(&self, op: &T::Op) -> Result<AddCtx, Error> {
+        self.crdt.validate_local(op, self.actor_id)?;
+        Ok(self.next_ctx())
+    }
+
+    /// Applies a group of operations locally, all or nothing, and returns them as
+    /// a single batch ready to be sent over the network.
+    ///
+    /// The closure applies operations through the [`Transaction`], which checks
+    /// each of them like [`Replica::try_apply`] against the state left by the
+    /// previous ones. If it returns an error, the replica is left untouched.
+    /// Returns `None` if no operation was applied.
+    pub fn transaction<F>(&mut self, f: F) -> Result<Option<Batch<T>>, Error>
+    where
+        T: Clone,
+        F: FnOnce(&mut Transaction<T>) -> Result<(), Error>,
+    {
+        let mut tx = self.begin_transaction();
+        f(&mut tx)?;
+
+        let (crdt, clock, batch) = tx.into_parts();
+        if let Some(batch) = &batch {
+            self.commit_transaction(crdt, clock, batch);
+        }
+        Ok(batch)
+    }
+
+    /// Starts a transaction on a copy of this replica's state, which only
+    /// reaches the replica through [`Replica::commit_transaction`].
+    pub(crate) fn begin_transaction(&self) -> Transaction<T>
+    where
+        T: Clone,
+    {
+        Transaction::new(
+            self.actor_id,
+            self.op_counter,
+            self.next_counter(),
+            self.clock.clone(),
+            self.crdt.clone(),
+        )
+    }
+
+    /// Replaces the state and clock with those left by a transaction that
+    /// produced the batch.
+    pub(crate) fn commit_transaction(&mut self, crdt: T, clock: VClock, batch: &Batch<T>) {
+        let last = batch.dots().last().expect("a batch is never empty");
+        self.op_counter = last.counter;
+        self.applied.insert_up_to(last);
+        self.crdt = crdt;
+        self.clock = clock;
     }
 
     /// Applies a remote operation and merges its causal context.
-    pub fn apply_remote(&mut self, op: T::Op, ctx: AddCtx) {
-        // 1. Apply the operation to the underlying CRDT.
-        self.crdt.apply(op, ctx.clone());
+    ///
+    /// An operation that has already been applied is skipped. With causal delivery
+    /// enabled, an operation whose predecessors have not been applied yet is
+    /// buffered instead.
+    pub fn apply_remote(&mut self, op: T::Op, ctx: AddCtx) -> ApplyOutcome {
+        self.apply_remote_reporting(op, ctx, &mut |_, _| {})
+    }
 
-        // 2. Merge the incoming clock to update our own causal knowledge.
-        self.clock.merge(ctx.clock);
+    /// Applies a remote operation like [`Replica::apply_remote`], and reports
+    /// every operation that reaches the CRDT: this one unless it is buffered,
+    /// and every buffered operation it releases.
+    pub(crate) fn apply_remote_reporting(
+        &mut self,
+        op: T::Op,
+        ctx: AddCtx,
+        delivered: &mut dyn FnMut(&T::Op, &AddCtx),
+    ) -> ApplyOutcome {
+        if self.applied.contains(&ctx.dot) {
+            return ApplyOutcome::Duplicate;
+        }
+
+        if !self.causal_delivery {
+            self.deliver(op, ctx, delivered);
+            return ApplyOutcome::Applied;
+        }
+
+        if self.is_causally_ready(&ctx) {
+            self.deliver(op, ctx, delivered);
+            self.drain_pending(delivered);
+            ApplyOutcome::Applied
+        } else {
+            self.pending.insert(ctx.dot, (op, ctx));
+            ApplyOutcome::Buffered
+        }
+    }
+
+    /// Applies every operation of a remote batch at once.
+    ///
+    /// The outcome is the same for every operation in the batch: with causal
+    /// delivery, the whole batch is buffered until its first operation is ready,
+    /// and the rest then become ready in turn, so they are all applied together.
+    pub fn apply_remote_batch(&mut self, batch: Batch<T>) -> ApplyOutcome {
+        self.apply_remote_ops(batch.into_ops())
+    }
+
+    /// Validates every operation of a remote batch like
+    /// [`Replica::try_apply_remote`], and applies the batch like
+    /// [`Replica::apply_remote_batch`] if all of them are valid.
+    ///
+    /// Nothing is applied, buffered or recorded when an error is returned, even
+    /// if only the last operation of the batch is invalid.
+    pub fn try_apply_remote_batch(&mut self, batch: Batch<T>) -> Result<ApplyOutcome, Error> {
+        let ops = batch.into_ops();
+        for (op, ctx) in &ops {
+            self.check_remote(op, ctx)?;
+        }
+        Ok(self.apply_remote_ops(ops))
+    }
+
+    /// Applies the operations of a batch, and returns the outcome shared by
+    /// all of them.
+    pub(crate) fn apply_remote_ops(&mut self, ops: Vec<(T::Op, AddCtx)>) -> ApplyOutcome {
+        let mut outcome = ApplyOutcome::Duplicate;
+        for (op, ctx) in ops {
+            match self.apply_remote(op, ctx) {
+                ApplyOutcome::Duplicate => {}
+                applied_or_buffered => outcome = applied_or_buffered,
+            }
+        }
+        outcome
+    }
+
+    /// Validates a remote operation and its context, and applies it like
+    /// [`Replica::apply_remote`] if both are valid.
+    ///
+    /// With a physical clock, an operation whose timestamp, or any timestamp its
+    /// clock references, is further ahead of it than the allowed drift is
+    /// rejected as well.
+    ///
+    /// Nothing is applied, buffered or recorded when an error is returned.
+    pub fn try_apply_remote(&mut self, op: T::Op, ctx: AddCtx) -> Result<ApplyOutcome, Error> {
+        self.check_remote(&op, &ctx)?;
+        Ok(self.apply_remote(op, ctx))
+    }
+
+    /// Checks a remote operation and its context without applying it.
+    pub(crate) fn check_remote(&self, op: &T::Op, ctx: &AddCtx) -> Result<(), Error> {
+        ctx.validate()?;
+        if let Some(hlc) = &self.hlc {
+            hlc.check_ctx(ctx)?;
+        }
+        self.crdt.validate(op, ctx)
+    }
+
+    /// Returns `true` if the dot has been applied or is buffered, so that
+    /// receiving it again changes nothing.
+    pub(crate) fn has_received(&self, dot: &Dot) -> bool {
+        self.applied.contains(dot) || self.pending.contains_key(dot)
+    }
+
+    /// Returns the buffered operations that are waiting for their causal
+    /// predecessors, in dot order.
+    pub fn pending(&self) -> impl Iterator<Item = &(T::Op, AddCtx)> {
+        self.pending.values()
     }
 
     pub fn read(&self) -> T::Value {
@@ -83,8 +351,24 @@ impl<T: CmRDT> Replica<T> {
     }
 
     pub fn merge(&mut self, remote_crdt: T, remote_clock: VClock) {
+        self.merge_reporting(remote_crdt, remote_clock, &mut |_, _| {});
+    }
+
+    /// Merges a remote state like [`Replica::merge`], and reports every
+    /// buffered operation the merge releases.
+    pub(crate) fn merge_reporting(
+        &mut self,
+        remote_crdt: T,
+        remote_clock: VClock,
+        delivered: &mut dyn FnMut(&T::Op, &AddCtx),
+    ) {
         self.crdt.merge(remote_crdt);
         self.clock.merge(remote_clock);
+
+        if self.causal_delivery {
+            // The merged clock may unblock some buffered ops.
+            self.drain_pending(delivered);
+        }
     }
 
     pub fn state(&self) -> &T {
@@ -94,4 +378,793 @@ impl<T: CmRDT> Replica<T> {
     pub fn clock(&self) -> &VClock {
         &self.clock
     }
+
+    /// Registers a peer whose clock is not known yet. Until it is observed, the
+    /// peer holds the stable frontier back to the empty clock.
+    pub fn add_peer(&mut self, actor: ActorId) {
+        if actor != self.actor_id {
+            self.peers.entry(actor).or_default();
+        }
+    }
+
+    /// Records that the peer has observed every event covered by the clock, for
+    /// example from an acknowledgement or a sync handshake.
+    pub fn observe_peer(&mut self, actor: ActorId, clock: VClock) {
+        if actor != self.actor_id {
+            self.peers.entry(actor).or_default().merge(clock);
+        }
+    }
+
+    /// Returns the latest clock known for each peer.
+    pub fn peers(&self) -> &BTreeMap<ActorId, VClock> {
+        &self.peers
+    }
+
+    /// Returns the causally stable frontier: the pointwise minimum of this
+    /// replica's clock and the clock of every known peer.
+    ///
+    /// Until a peer is known, the frontier is empty: a replica that has never
+    /// heard of its peers cannot tell that they have observed anything.
+    pub fn stable_clock(&self) -> VClock {
+        if self.peers.is_empty() {
+            return VClock::default();
+        }
+        let mut stable = self.clock.clone();
+        for clock in self.peers.values() {
+            stable.glb(clock);
+        }
+        stable
+    }
+
+    /// Lets the CRDT discard the metadata of events below the stable frontier.
+    pub fn purge(&mut self) {
+        let stable = self.stable_clock();
+        self.crdt.purge(&stable);
+    }
+
+    /// Returns the record of every dot this replica has applied.
+    pub fn applied(&self) -> &AppliedDots {
+        &self.applied
+    }
+
+    /// Captures everything needed to restore this replica after a restart.
+    pub fn snapshot(&self) -> Snapshot<T>
+    where
+        T: Clone,
+    {
+        Snapshot {
+            op_counter: self.op_counter,
+            clock: self.clock.clone(),
+            applied: self.applied.clone(),
+            peers: self.peers.clone(),
+            pending: self.pending.values().cloned().collect(),
+            state: self.crdt.clone(),
+        }
+    }
+
+    /// Replaces the state of this replica with a snapshot taken by
+    /// [`Replica::snapshot`], keeping its actor and configuration.
+    pub fn restore(&mut self, snapshot: Snapshot<T>) {
+        self.op_counter = snapshot.op_counter;
+        self.clock = snapshot.clock;
+        self.applied = snapshot.applied;
+        self.peers = snapshot.peers;
+        self.pending = snapshot
+            .pending
+            .into_iter()
+            .map(|(op, ctx)| (ctx.dot, (op, ctx)))
+            .collect();
+        self.crdt = snapshot.state;
+    }
+
+    /// Re-applies an operation recovered from storage, as returned by
+    /// [`Replica::apply`] or given to [`Replica::apply_remote`] before a restart.
+    ///
+    /// Operations from other actors are applied like [`Replica::apply_remote`].
+    /// This replica's own operations keep their original dot, and advance the
+    /// counter past it so that new operations never reuse it.
+    pub fn replay(&mut self, op: T::Op, ctx: AddCtx) -> ApplyOutcome {
+        if ctx.dot.actor != self.actor_id {
+            return self.apply_remote(op, ctx);
+        }
+        if self.applied.contains(&ctx.dot) {
+            return ApplyOutcome::Duplicate;
+        }
+
+        self.op_counter = self.op_counter.max(ctx.dot.counter);
+        self.applied.insert_up_to(ctx.dot);
+        self.crdt.apply(op, ctx.clone());
+        self.clock.merge(ctx.clock);
+        ApplyOutcome::Applied
+    }
+
+    /// Returns the counter for the next local operation.
+    fn next_counter(&self) -> u64 {
+        // 1. Find the latest "time" (counter) this replica has seen from any actor.
+        let latest_known_time = self.clock.max_counter();
+
+        // 2. Ensure our new op's counter is causally newer than both our last op
+        //    and any other op we've seen.
+        let latest = latest_known_time.max(self.op_counter);
+        match &self.hlc {
+            Some(hlc) => hlc.next(latest),
+            None => latest + 1,
+        }
+    }
+
+    fn deliver(&mut self, op: T::Op, ctx: AddCtx, delivered: &mut dyn FnMut(&T::Op, &AddCtx)) {
+        // 1. Apply the operation to the underlying CRDT.
+        delivered(&op, &ctx);
+        self.crdt.apply(op, ctx.clone());
+
+        // 2. Record the dot so that redeliveries can be skipped, along with the
+        //    counters the sender skipped since its previous op.
+        self.applied.insert_after(ctx.dot, ctx.prev);
+
+        // 3. The sender had observed everything in the incoming clock.
+        self.observe_peer(ctx.dot.actor, ctx.clock.clone());
+
+        // 4. Merge the incoming clock to update our own causal knowledge.
+        self.clock.merge(ctx.clock);
+    }
+
+    /// Returns `true` if every event the context references, other than its own
+    /// dot, has already been applied.
+    fn is_causally_ready(&self, ctx: &AddCtx) -> bool {
+        let origin = ctx.dot.actor;
+        ctx.clock.0.iter().all(|(actor, counter)| {
+            // The sender's own entry is the op's dot, which follows `prev`.
+            let required = if *actor == origin { ctx.prev } else { *counter };
+            self.clock.get(actor) >= required
+        })
+    }
+
+    /// Applies buffered operations until none of the remaining ones are ready.
+    fn drain_pending(&mut self, delivered: &mut dyn FnMut(&T::Op, &AddCtx)) {
+        while let Some(dot) = self
+            .pending
+            .values()
+            .find(|(_, ctx)| self.is_causally_ready(ctx))
+            .map(|(_, ctx)| ctx.dot)
+        {
+            let (op, ctx) = self.pending.remove(&dot).expect("dot was just found");
+            if !self.applied.contains(&dot) {
+                self.deliver(op, ctx, delivered);
+            }
+        }
+    }
+}
+
+impl<T: InfallibleLocal> Replica<T> {
+    /// Applies an operation locally and returns the operation and its generated
+    /// context, ready to be sent over the network.
+    ///
+    /// CRDTs that may refuse a local operation use [`Replica::try_apply`]
+    /// instead.
+    pub fn apply(&mut self, op: T::Op) -> (T::Op, AddCtx) {
+        self.generate(op)
+    }
+}
+
+impl<T: DeltaCmRDT> Replica<T> {
+    /// Returns the part of this replica's state that a peer with the given clock
+    /// has not seen, along with this replica's clock, ready for
+    /// [`Replica::merge_delta`] on the peer.
+    ///
+    /// Every event the clock covers is taken as applied by the peer. Without
+    /// causal delivery, a clock may also cover operations the peer has only
+    /// heard of through the context of a later one, which the delta would then
+    /// leave out: use [`Replica::delta_since_applied`] with the peer's
+    /// [`Replica::applied`] instead.
+    pub fn delta_since(&self, clock: &VClock) -> (T, VClock) {
+        self.delta_since_applied(&AppliedDots::from(clock.clone()))
+    }
+
+    /// Returns the part of this replica's state that a peer with the given
+    /// applied dots has not applied, along with this replica's clock, ready for
+    /// [`Replica::merge_delta`] on the peer.
+    pub fn delta_since_applied(&self, applied: &AppliedDots) -> (T, VClock) {
+        (self.crdt.delta_since(applied), self.clock.clone())
+    }
+
+    /// Merges a delta produced by a peer's [`Replica::delta_since`] or
+    /// [`Replica::delta_since_applied`].
+    pub fn merge_delta(&mut self, delta: T, clock: VClock) {
+        self.merge(delta, clock);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lww_register::{self, LWWRegister};
+    use crate::or_set::{self, ORSet};
+    use std::collections::BTreeSet;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_causal_delivery_buffers_until_predecessors_arrive() {
+        // Arrange: B adds an element after observing A's add.
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default());
+
+        let first = replica_a.apply(or_set::Op::Add(1));
+        replica_b.apply_remote(first.0.clone(), first.1.clone());
+        let second = replica_b.apply(or_set::Op::Add(2));
+
+        let mut replica_c = Replica::new(ActorId(3), ORSet::default()).with_causal_delivery();
+
+        // Act: C receives B's op before the op it depends on.
+        let outcome = replica_c.apply_remote(second.0, second.1);
+
+        // Assert: the op is buffered and not yet visible.
+        assert_eq!(outcome, ApplyOutcome::Buffered);
+        assert!(replica_c.read().is_empty());
+        assert_eq!(replica_c.pending().count(), 1);
+
+        // Act: the missing predecessor arrives.
+        let outcome = replica_c.apply_remote(first.0, first.1);
+
+        // Assert: both ops are applied and the buffer is drained.
+        assert_eq!(outcome, ApplyOutcome::Applied);
+        assert_eq!(replica_c.read(), BTreeSet::from([1, 2]));
+        assert_eq!(replica_c.pending().count(), 0);
+    }
+
+    #[test]
+    fn test_causal_delivery_orders_ops_from_same_actor() {
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let add = replica_a.apply(or_set::Op::Add(1));
+        let rm = replica_a.apply(or_set::Op::Rm(1));
+
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default()).with_causal_delivery();
+        replica_b.apply_remote(rm.0, rm.1);
+        assert_eq!(replica_b.pending().count(), 1);
+
+        replica_b.apply_remote(add.0, add.1);
+        assert!(replica_b.read().is_empty());
+        assert_eq!(replica_b.pending().count(), 0);
+        assert_eq!(replica_b.clock(), replica_a.clock());
+    }
+
+    #[test]
+    fn test_causal_delivery_waits_for_previous_op_behind_counter_jump() {
+        // Arrange: A adds at A1, then learns of B5, so its remove jumps to A6
+        // with the clock {A: 6, B: 5}.
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default());
+        let add = replica_a.apply(or_set::Op::Add(1));
+        let b_ops: Vec<_> = (10..15)
+            .map(|value| replica_b.apply(or_set::Op::Add(value)))
+            .collect();
+        let (op, ctx) = b_ops.last().unwrap().clone();
+        replica_a.apply_remote(op, ctx);
+        let rm = replica_a.apply(or_set::Op::Rm(1));
+        assert_eq!(rm.1.dot.counter, 6);
+        assert_eq!(rm.1.prev, 1);
+
+        let mut replica_c = Replica::new(ActorId(3), ORSet::default()).with_causal_delivery();
+        for (op, ctx) in b_ops {
+            replica_c.apply_remote(op, ctx);
+        }
+
+        // Act: the remove arrives before the add it follows.
+        let outcome = replica_c.apply_remote(rm.0, rm.1);
+
+        // Assert
+        assert_eq!(outcome, ApplyOutcome::Buffered);
+        replica_c.apply_remote(add.0, add.1);
+        assert_eq!(replica_c.read(), (10..15).collect());
+        assert_eq!(replica_c.pending().count(), 0);
+    }
+
+    #[test]
+    fn test_causal_delivery_ignores_redelivered_ops() {
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let add = replica_a.apply(or_set::Op::Add(1));
+
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default()).with_causal_delivery();
+        let first = replica_b.apply_remote(add.0.clone(), add.1.clone());
+        let second = replica_b.apply_remote(add.0, add.1);
+
+        assert_eq!(first, ApplyOutcome::Applied);
+        assert_eq!(second, ApplyOutcome::Duplicate);
+        assert_eq!(replica_b.pending().count(), 0);
+    }
+
+    #[test]
+    fn test_redelivered_ops_are_skipped() {
+        // Arrange: a counter whose `apply` is not idempotent on its own.
+        #[derive(Debug, Clone, Default)]
+        struct Sum(u64);
+
+        impl CmRDT for Sum {
+            type Op = u64;
+            type Value = u64;
+
+            fn apply(&mut self, op: Self::Op, _ctx: AddCtx) {
+                self.0 += op;
+            }
+
+            fn merge(&mut self, _other: Self) {}
+
+            fn read(&self) -> Self::Value {
+                self.0
+            }
+        }
+
+        impl InfallibleLocal for Sum {}
+
+        let mut replica_a = Replica::new(ActorId(1), Sum::default());
+        let ops: Vec<_> = (1..=3).map(|n| replica_a.apply(n)).collect();
+
+        let mut replica_b = Replica::new(ActorId(2), Sum::default());
+
+        // Act: deliver every op twice, out of order.
+        let mut outcomes = Vec::new();
+        for (op, ctx) in ops.iter().rev().chain(ops.iter()) {
+            outcomes.push(replica_b.apply_remote(*op, ctx.clone()));
+        }
+
+        // Assert: each op was applied exactly once.
+        assert_eq!(replica_b.read(), 6);
+        assert_eq!(
+            outcomes
+                .iter()
+                .filter(|o| **o == ApplyOutcome::Applied)
+                .count(),
+            3
+        );
+        assert_eq!(
+            outcomes
+                .iter()
+                .filter(|o| **o == ApplyOutcome::Duplicate)
+                .count(),
+            3
+        );
+        assert!(replica_b.applied().exceptions.is_empty());
+    }
+
+    #[test]
+    fn test_merge_drains_pending_ops() {
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        replica_a.apply(or_set::Op::Add(1));
+        let later = replica_a.apply(or_set::Op::Add(2));
+
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default()).with_causal_delivery();
+        replica_b.apply_remote(later.0, later.1);
+        assert_eq!(replica_b.pending().count(), 1);
+
+        replica_b.merge(replica_a.state().clone(), replica_a.clock().clone());
+
+        assert_eq!(replica_b.read(), BTreeSet::from([1, 2]));
+        assert_eq!(replica_b.pending().count(), 0);
+    }
+
+    #[test]
+    fn test_default_mode_applies_immediately() {
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        replica_a.apply(or_set::Op::Add(1));
+        let later = replica_a.apply(or_set::Op::Add(2));
+
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default());
+        replica_b.apply_remote(later.0, later.1);
+
+        assert_eq!(replica_b.read(), BTreeSet::from([2]));
+        assert_eq!(replica_b.pending().count(), 0);
+    }
+
+    #[test]
+    fn test_try_apply_remote_rejects_inconsistent_context() {
+        // Arrange: a context whose clock does not cover its own dot.
+        let dot = Dot {
+            actor: ActorId(1),
+            counter: 3,
+        };
+        let ctx = AddCtx {
+            dot,
+            prev: 0,
+            clock: VClock::default(),
+        };
+        let mut replica = Replica::new(ActorId(2), ORSet::default());
+
+        // Act
+        let result = replica.try_apply_remote(or_set::Op::Add(1), ctx.clone());
+
+        // Assert: nothing was applied or recorded, so a valid copy still applies.
+        assert_eq!(result, Err(Error::DotNotInClock(dot)));
+        assert!(replica.read().is_empty());
+        assert!(!replica.applied().contains(&dot));
+
+        let mut valid = ctx;
+        valid.clock.0.insert(dot.actor, dot.counter);
+        assert_eq!(
+            replica.try_apply_remote(or_set::Op::Add(1), valid),
+            Ok(ApplyOutcome::Applied)
+        );
+    }
+
+    #[test]
+    fn test_stable_clock_is_pointwise_minimum_of_peers() {
+        // Arrange: B has seen A's first add, A has seen nothing from B.
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default());
+
+        let first = replica_a.apply(or_set::Op::Add(1));
+        replica_b.apply_remote(first.0, first.1);
+        replica_a.apply(or_set::Op::Add(2));
+        let from_b = replica_b.apply(or_set::Op::Add(3));
+
+        // Act
+        replica_a.apply_remote(from_b.0, from_b.1.clone());
+
+        // Assert: A's second add is the only event not known to both.
+        assert_eq!(replica_a.peers()[&ActorId(2)], from_b.1.clock);
+        assert_eq!(
+            replica_a.stable_clock(),
+            VClock(BTreeMap::from([(ActorId(1), 1), (ActorId(2), 2)]))
+        );
+    }
+
+    #[test]
+    fn test_unobserved_peer_holds_back_stable_clock() {
+        let mut replica = Replica::new(ActorId(1), ORSet::default());
+        replica.apply(or_set::Op::Add(1));
+        assert_eq!(replica.stable_clock(), VClock::default());
+
+        replica.add_peer(ActorId(2));
+        assert_eq!(replica.stable_clock(), VClock::default());
+
+        replica.observe_peer(ActorId(2), replica.clock().clone());
+        assert_eq!(&replica.stable_clock(), replica.clock());
+    }
+
+    #[test]
+    fn test_purge_drops_stable_tombstones() {
+        // Arrange
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default());
+        replica_a.add_peer(ActorId(2));
+
+        for op in [or_set::Op::Add(1), or_set::Op::Rm(1)] {
+            let (op, ctx) = replica_a.apply(op);
+            replica_b.apply_remote(op, ctx);
+        }
+
+        // Act: the remove is not stable until B is known to have seen it.
+        replica_a.purge();
+        let before_ack = replica_a.state().tombstones.len();
+        replica_a.observe_peer(ActorId(2), replica_b.clock().clone());
+        replica_a.purge();
+
+        // Assert
+        assert_eq!(before_ack, 1);
+        assert!(replica_a.state().tombstones.is_empty());
+        assert!(replica_a.read().is_empty());
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct FakeClock(Arc<AtomicU64>);
+
+    impl FakeClock {
+        fn set(&self, millis: u64) {
+            self.0.store(millis, Ordering::SeqCst);
+        }
+    }
+
+    impl PhysicalClock for FakeClock {
+        fn now_millis(&self) -> u64 {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+
+    #[test]
+    fn test_physical_clock_orders_writes_by_real_time() {
+        // Arrange: A writes many times early on, B writes once later without
+        // having seen any of A's writes.
+        let time_a = FakeClock::default();
+        let time_b = FakeClock::default();
+        let mut replica_a = Replica::new(ActorId(2), LWWRegister::default())
+            .with_physical_clock(time_a.clone(), 1_000);
+        let mut replica_b = Replica::new(ActorId(1), LWWRegister::default())
+            .with_physical_clock(time_b.clone(), 1_000);
+
+        time_a.set(1_000);
+        let writes: Vec<_> = (0..5)
+            .map(|n| replica_a.apply(lww_register::Op::Set(n)))
+            .collect();
+        time_b.set(2_000);
+        let late = replica_b.apply(lww_register::Op::Set(99));
+
+        // Act
+        for (op, ctx) in writes {
+            replica_b.apply_remote(op, ctx);
+        }
+        replica_a.apply_remote(late.0, late.1.clone());
+
+        // Assert: the later write wins on both sides.
+        assert_eq!(Hlc::physical_ms(late.1.dot.counter), 2_000);
+        assert_eq!(replica_a.read(), Some(99));
+        assert_eq!(replica_b.read(), Some(99));
+    }
+
+    #[test]
+    fn test_physical_clock_rejects_excessive_drift() {
+        let time_a = FakeClock::default();
+        let time_b = FakeClock::default();
+        let mut replica_a = Replica::new(ActorId(1), LWWRegister::default())
+            .with_physical_clock(time_a.clone(), 1_000);
+        let mut replica_b = Replica::new(ActorId(2), LWWRegister::default())
+            .with_physical_clock(time_b.clone(), 1_000);
+
+        time_a.set(10_000);
+        time_b.set(8_000);
+        let (op, ctx) = replica_a.apply(lww_register::Op::Set(1));
+
+        assert_eq!(
+            replica_b.try_apply_remote(op.clone(), ctx.clone()),
+            Err(Error::ClockDrift(ctx.dot))
+        );
+        assert_eq!(replica_b.read(), None);
+
+        time_b.set(9_000);
+        assert_eq!(
+            replica_b.try_apply_remote(op, ctx),
+            Ok(ApplyOutcome::Applied)
+        );
+
+        // B's next write is still ordered after the one it observed, even though
+        // its physical clock is behind.
+        let (_, next) = replica_b.apply(lww_register::Op::Set(2));
+        assert!(next.dot.counter > 10_000 << crate::core::LOGICAL_BITS);
+    }
+
+    #[test]
+    fn test_physical_clock_rejects_drift_of_another_actor_in_clock() {
+        // Arrange: A's own timestamp is current, but its clock carries a
+        // far-future entry for a third actor.
+        let time = FakeClock::default();
+        let mut replica_a = Replica::new(ActorId(1), LWWRegister::default())
+            .with_physical_clock(time.clone(), 1_000);
+        let mut replica_b = Replica::new(ActorId(2), LWWRegister::default())
+            .with_physical_clock(time.clone(), 1_000);
+        time.set(10_000);
+        let (op, mut ctx) = replica_a.apply(lww_register::Op::Set(1));
+        let poisoned = Dot {
+            actor: ActorId(3),
+            counter: 1_000_000 << crate::core::LOGICAL_BITS,
+        };
+        ctx.clock.0.insert(poisoned.actor, poisoned.counter);
+
+        // Act
+        let outcome = replica_b.try_apply_remote(op, ctx);
+
+        // Assert: B's later timestamps are not dragged into the future.
+        assert_eq!(outcome, Err(Error::ClockDrift(poisoned)));
+        assert_eq!(replica_b.clock().get(&ActorId(3)), 0);
+        let (_, next) = replica_b.apply(lww_register::Op::Set(2));
+        assert_eq!(Hlc::physical_ms(next.dot.counter), 10_000);
+    }
+
+    #[test]
+    fn test_skipped_counters_leave_no_exceptions() {
+        let time = FakeClock::default();
+        let logical = |id| Replica::new(ActorId(id), ORSet::default());
+        let physical = |id| logical(id).with_physical_clock(time.clone(), 1_000);
+
+        for new_replica in [&logical as &dyn Fn(u64) -> Replica<ORSet<u32>>, &physical] {
+            // Arrange: A and B take turns, so each skips the counters of the other.
+            let mut replica_a = new_replica(1);
+            let mut replica_b = new_replica(2);
+            let mut replica_c = new_replica(3);
+            let mut ops = Vec::new();
+            for value in 0..50 {
+                time.set(value);
+                let (op, ctx) = replica_a.apply(or_set::Op::Add(value as u32));
+                replica_b.apply_remote(op.clone(), ctx.clone());
+                ops.push((op, ctx));
+                let (op, ctx) = replica_b.apply(or_set::Op::Rm(value as u32));
+                replica_a.apply_remote(op.clone(), ctx.clone());
+                ops.push((op, ctx));
+            }
+
+            // Act: C receives every op, the latest first.
+            for (op, ctx) in ops.into_iter().rev() {
+                replica_c.apply_remote(op, ctx);
+            }
+
+            // Assert
+            assert!(replica_c.applied().exceptions.is_empty());
+            assert_eq!(replica_c.applied().clock, *replica_a.clock());
+        }
+    }
+
+    #[test]
+    fn test_physical_clock_with_causal_delivery() {
+        let time = FakeClock::default();
+        let mut replica_a =
+            Replica::new(ActorId(1), ORSet::default()).with_physical_clock(time.clone(), 0);
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default())
+            .with_physical_clock(time.clone(), 0)
+            .with_causal_delivery();
+
+        time.set(5);
+        let (op, ctx) = replica_a.apply(or_set::Op::Add(1));
+
+        assert_eq!(replica_b.apply_remote(op, ctx), ApplyOutcome::Applied);
+        assert_eq!(replica_b.read(), BTreeSet::from([1]));
+    }
+
+    #[test]
+    fn test_transaction_is_applied_as_one_batch() {
+        // Arrange
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default());
+
+        // Act: a remove between two adds only observes the first one.
+        let batch = replica_a
+            .transaction(|tx| {
+                tx.apply(or_set::Op::Add(1))?;
+                tx.apply(or_set::Op::Rm(1))?;
+                tx.apply(or_set::Op::Add(1))?;
+                tx.apply(or_set::Op::Add(2))?;
+                Ok(())
+            })
+            .unwrap()
+            .unwrap();
+        let outcome = replica_b.apply_remote_batch(batch.clone());
+
+        // Assert
+        assert_eq!(batch.ops.len(), 4);
+        assert_eq!(outcome, ApplyOutcome::Applied);
+        assert_eq!(replica_a.read(), BTreeSet::from([1, 2]));
+        assert_eq!(replica_b.state(), replica_a.state());
+        assert_eq!(replica_b.clock(), replica_a.clock());
+        assert_eq!(replica_b.apply_remote_batch(batch), ApplyOutcome::Duplicate);
+
+        // The next op is ordered after the whole batch.
+        let (_, ctx) = replica_a.apply(or_set::Op::Add(3));
+        assert_eq!(ctx.dot.counter, 5);
+    }
+
+    #[test]
+    fn test_failed_transaction_leaves_replica_untouched() {
+        use crate::bounded_counter::{self, BoundedCounter};
+
+        // Arrange
+        let mut replica = Replica::new(ActorId(1), BoundedCounter::default());
+        replica.try_apply(bounded_counter::Op::Inc(5)).unwrap();
+        let before = replica.clone();
+
+        // Act: the second decrement exceeds the rights left by the first.
+        let result = replica.transaction(|tx| {
+            tx.apply(bounded_counter::Op::Dec(3))?;
+            assert_eq!(tx.state().read(), 2);
+            tx.apply(bounded_counter::Op::Dec(3))?;
+            Ok(())
+        });
+
+        // Assert
+        assert_eq!(result.unwrap_err(), Error::InsufficientRights(ActorId(1)));
+        assert_eq!(replica.state(), before.state());
+        assert_eq!(replica.clock(), before.clock());
+        assert_eq!(
+            replica
+                .try_apply(bounded_counter::Op::Dec(5))
+                .unwrap()
+                .1
+                .dot
+                .counter,
+            2
+        );
+    }
+
+    #[test]
+    fn test_empty_transaction_returns_no_batch() {
+        let mut replica = Replica::new(ActorId(1), ORSet::<u8>::default());
+        assert_eq!(replica.transaction(|_| Ok(())), Ok(None));
+        assert_eq!(replica.clock(), &VClock::default());
+    }
+
+    #[test]
+    fn test_causal_delivery_buffers_whole_batch() {
+        // Arrange: the batch depends on an add B has not received yet.
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let first = replica_a.apply(or_set::Op::Add(1));
+        let batch = replica_a
+            .transaction(|tx| {
+                tx.apply(or_set::Op::Rm(1))?;
+                tx.apply(or_set::Op::Add(2))?;
+                Ok(())
+            })
+            .unwrap()
+            .unwrap();
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default()).with_causal_delivery();
+
+        // Act & Assert
+        assert_eq!(replica_b.apply_remote_batch(batch), ApplyOutcome::Buffered);
+        assert!(replica_b.read().is_empty());
+        assert_eq!(replica_b.pending().count(), 2);
+
+        replica_b.apply_remote(first.0, first.1);
+        assert_eq!(replica_b.read(), BTreeSet::from([2]));
+        assert_eq!(replica_b.pending().count(), 0);
+    }
+
+    #[test]
+    fn test_try_apply_remote_batch_rejects_whole_batch() {
+        use crate::lww_map::{self, LWWMap};
+
+        // Arrange: B already recorded a different write with the batch's last dot.
+        let mut replica_a = Replica::new(ActorId(1), LWWMap::default());
+        let batch = replica_a
+            .transaction(|tx| {
+                tx.apply(lww_map::Op::Set("a", 1))?;
+                tx.apply(lww_map::Op::Set("a", 2))?;
+                tx.apply(lww_map::Op::Set("b", 3))?;
+                Ok(())
+            })
+            .unwrap()
+            .unwrap();
+        let (_, last) = batch.clone().into_ops().pop().unwrap();
+        let mut replica_b = Replica::new(ActorId(2), LWWMap::default());
+        replica_b.apply_remote(lww_map::Op::Set("b", 9), last.clone());
+
+        // Act
+        let result = replica_b.try_apply_remote_batch(batch);
+
+        // Assert: not even the valid operations before it were applied.
+        assert_eq!(result, Err(Error::ConflictingDot(last.dot)));
+        assert_eq!(replica_b.read(), BTreeMap::from([("b", 9)]));
+        assert!(!replica_b.applied().contains(&Dot {
+            actor: ActorId(1),
+            counter: 1,
+        }));
+    }
+
+    #[test]
+    fn test_restore_from_snapshot() {
+        // Arrange: a replica with a buffered op and a known peer.
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let (op1, ctx1) = replica_a.apply(or_set::Op::Add(1));
+        let (op2, ctx2) = replica_a.apply(or_set::Op::Add(2));
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default()).with_causal_delivery();
+        replica_b.apply(or_set::Op::Add(3));
+        replica_b.apply_remote(op2, ctx2);
+        replica_b.add_peer(ActorId(3));
+
+        // Act
+        let mut restored = Replica::new(ActorId(2), ORSet::default()).with_causal_delivery();
+        restored.restore(replica_b.snapshot());
+        restored.apply_remote(op1, ctx1);
+
+        // Assert
+        assert_eq!(restored.read(), BTreeSet::from([1, 2, 3]));
+        assert_eq!(restored.pending().count(), 0);
+        assert!(restored.peers().contains_key(&ActorId(3)));
+    }
+
+    #[test]
+    fn test_replay_keeps_own_dots() {
+        // Arrange: the ops a replica generated before a restart.
+        let mut before = Replica::new(ActorId(1), ORSet::default());
+        let ops = vec![
+            before.apply(or_set::Op::Add(1)),
+            before.apply(or_set::Op::Add(2)),
+        ];
+
+        // Act
+        let mut after = Replica::new(ActorId(1), ORSet::default());
+        for (op, ctx) in ops.clone() {
+            assert_eq!(after.replay(op, ctx), ApplyOutcome::Applied);
+        }
+
+        // Assert: replays are idempotent and no dot is reused.
+        let (op, ctx) = ops[1].clone();
+        assert_eq!(after.replay(op, ctx), ApplyOutcome::Duplicate);
+        assert_eq!(after.state(), before.state());
+        assert_eq!(after.applied(), before.applied());
+        assert_eq!(after.apply(or_set::Op::Add(3)).1.dot.counter, 3);
+    }
 }
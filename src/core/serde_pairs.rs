@@ -0,0 +1,27 @@
+//! Serializes a map as a sequence of `(key, value)` pairs.
+//!
+//! Formats such as JSON only accept strings as map keys, so a map keyed by a
+//! [`Dot`](crate::Dot) or any other struct is written as a list of pairs
+//! instead, with `#[serde(with = "crate::core::serde_pairs")]`.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+pub fn serialize<K, V, S>(map: &BTreeMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Serialize,
+    V: Serialize,
+    S: Serializer,
+{
+    serializer.collect_seq(map)
+}
+
+pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<BTreeMap<K, V>, D::Error>
+where
+    K: Ord + Deserialize<'de>,
+    V: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let pairs = Vec::<(K, V)>::deserialize(deserializer)?;
+    Ok(pairs.into_iter().collect())
+}
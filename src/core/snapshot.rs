@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::core::{ActorId, AddCtx, AppliedDots, CmRDT, VClock};
+
+/// Everything a [`Replica`](crate::core::Replica) needs to resume where it left
+/// off: its state, its causal knowledge and the last counter it generated.
+///
+/// The configuration of the replica, such as its actor, causal delivery or
+/// physical clock, is not part of the snapshot. It is restored into a replica
+/// configured by the caller with `Replica::restore`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize, T::Op: Serialize",
+    deserialize = "T: Deserialize<'de>, T::Op: Deserialize<'de>"
+))]
+pub struct Snapshot<T: CmRDT> {
+    pub op_counter: u64,
+    pub clock: VClock,
+    pub applied: AppliedDots,
+    pub peers: BTreeMap<ActorId, VClock>,
+    /// The operations that were buffered for causal delivery, in dot order.
+    pub pending: Vec<(T::Op, AddCtx)>,
+    pub state: T,
+}
@@ -1,4 +1,5 @@
-use crate::core::AddCtx;
+use crate::Error;
+use crate::core::{ActorId, AddCtx, AppliedDots, VClock};
 
 /// The core trait for all CmRDTs.
 pub trait CmRDT {
@@ -16,4 +17,70 @@ pub trait CmRDT {
 
     /// Read the current value of the CRDT.
     fn read(&self) -> Self::Value;
+
+    /// Check that an operation can be applied to the CRDT, without applying it.
+    ///
+    /// The context itself has already been validated by the time this is called.
+    /// Types that can receive malformed operations override this to reject them.
+    fn validate(&self, _op: &Self::Op, _ctx: &AddCtx) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Check that the replica of the given actor may generate an operation.
+    ///
+    /// Unlike `validate`, this is only used for local operations, before they are
+    /// assigned a dot. Types whose ops can be refused locally override this.
+    fn validate_local(&self, _op: &Self::Op, _actor: ActorId) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Discard the metadata that is only needed for events not covered by the
+    /// given causally stable clock.
+    ///
+    /// Every replica must already have applied every event the clock covers, and
+    /// every event concurrent with them, so that none of them can still arrive.
+    /// Types without such metadata keep the default, which does nothing.
+    fn purge(&mut self, _stable: &VClock) {}
+
+    /// Validate the context and the operation, and apply it if both are valid.
+    fn try_apply(&mut self, op: Self::Op, ctx: AddCtx) -> Result<(), Error> {
+        ctx.validate()?;
+        self.validate(&op, &ctx)?;
+        self.apply(op, ctx);
+        Ok(())
+    }
+}
+
+/// A CmRDT that never refuses a local operation, as it keeps the default
+/// [`CmRDT::validate_local`].
+///
+/// Only these types can be used with [`Replica::apply`](crate::core::Replica::apply),
+/// which has no error to return. Operations on the others, such as a
+/// `BoundedCounter` decrement, go through
+/// [`Replica::try_apply`](crate::core::Replica::try_apply).
+pub trait InfallibleLocal: CmRDT {}
+
+/// A CmRDT that can produce the part of its state a peer has not seen yet.
+///
+/// A delta is an ordinary (partial) state of the same type, so it is applied
+/// with `CmRDT::merge`. Merging the delta into the peer whose applied dots were
+/// used to compute it must give the same result as merging the full state.
+///
+/// A delta is computed from the dots the peer has applied rather than from its
+/// clock: without causal delivery, a clock also covers the events the peer
+/// only heard of through the context of another operation.
+pub trait DeltaCmRDT: CmRDT + Sized {
+    /// Returns the smallest state fragment covering every event the peer has
+    /// not applied.
+    fn delta_since(&self, applied: &AppliedDots) -> Self;
+}
+
+/// A join-semilattice: a set of states in which any two states have a least
+/// upper bound.
+///
+/// `join` must be commutative, associative and idempotent, which is exactly what
+/// makes a lattice state safe to replicate with `LatticeRegister`.
+pub trait Lattice {
+    /// Replaces this state with the least upper bound of both states.
+    fn join(&mut self, other: Self);
 }
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+use crate::core::{ActorId, AddCtx, CmRDT, Dot, VClock};
+
+/// Operations with their contexts, in the order they were applied.
+pub type Ops<T> = Vec<(<T as CmRDT>::Op, AddCtx)>;
+
+/// A group of operations generated together by one replica, sharing a single
+/// causal context.
+///
+/// The operations are assigned consecutive dots, starting with `ctx.dot`, which
+/// follows `ctx.prev`, and `ctx.clock` is the clock of the replica after the
+/// last of them. The context of each operation is rebuilt from these by
+/// [`Batch::contexts`], exactly as `Replica::apply` would have generated it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T::Op: Serialize",
+    deserialize = "T::Op: Deserialize<'de>"
+))]
+pub struct Batch<T: CmRDT> {
+    pub ops: Vec<T::Op>,
+    pub ctx: AddCtx,
+}
+
+impl<T: CmRDT> Batch<T> {
+    /// Returns the dot of every operation in the batch.
+    pub fn dots(&self) -> impl Iterator<Item = Dot> + '_ {
+        let first = self.ctx.dot;
+        (0..self.ops.len() as u64).map(move |i| Dot {
+            actor: first.actor,
+            counter: first.counter + i,
+        })
+    }
+
+    /// Returns the context of every operation in the batch.
+    pub fn contexts(&self) -> impl Iterator<Item = AddCtx> + '_ {
+        let first = self.ctx.dot;
+        self.dots().map(move |dot| {
+            let prev = if dot == first {
+                self.ctx.prev
+            } else {
+                dot.counter - 1
+            };
+            let mut clock = self.ctx.clock.clone();
+            clock.0.insert(dot.actor, dot.counter);
+            AddCtx { dot, prev, clock }
+        })
+    }
+
+    /// Splits the batch into its operations, each with its own context.
+    pub fn into_ops(self) -> Ops<T> {
+        let contexts: Vec<AddCtx> = self.contexts().collect();
+        self.ops.into_iter().zip(contexts).collect()
+    }
+}
+
+/// The operations of a transaction in progress, applied to a copy of the
+/// replica's state.
+///
+/// Nothing reaches the replica until the closure passed to
+/// `Replica::transaction` returns successfully.
+#[derive(Debug)]
+pub struct Transaction<T: CmRDT> {
+    actor: ActorId,
+    prev: u64,
+    first_counter: u64,
+    clock: VClock,
+    crdt: T,
+    ops: Vec<T::Op>,
+}
+
+impl<T: CmRDT> Transaction<T> {
+    pub(crate) fn new(
+        actor: ActorId,
+        prev: u64,
+        first_counter: u64,
+        clock: VClock,
+        crdt: T,
+    ) -> Self {
+        Self {
+            actor,
+            prev,
+            first_counter,
+            clock,
+            crdt,
+            ops: Vec::new(),
+        }
+    }
+
+    /// Checks that the replica may generate the operation, and applies it to
+    /// the transaction's copy of the state. Returns the dot assigned to it.
+    pub fn apply(&mut self, op: T::Op) -> Result<Dot, Error> {
+        self.crdt.validate_local(&op, self.actor)?;
+
+        let dot = Dot {
+            actor: self.actor,
+            counter: self.first_counter + self.ops.len() as u64,
+        };
+        let prev = if self.ops.is_empty() {
+            self.prev
+        } else {
+            dot.counter - 1
+        };
+        self.clock.0.insert(dot.actor, dot.counter);
+        let ctx = AddCtx {
+            dot,
+            prev,
+            clock: self.clock.clone(),
+        };
+
+        self.crdt.apply(op.clone(), ctx);
+        self.ops.push(op);
+        Ok(dot)
+    }
+
+    /// Returns the state with every operation of the transaction so far applied.
+    pub fn state(&self) -> &T {
+        &self.crdt
+    }
+
+    /// Returns the final state and clock, and the batch to send to peers, if
+    /// any operation was applied.
+    pub(crate) fn into_parts(self) -> (T, VClock, Option<Batch<T>>) {
+        if self.ops.is_empty() {
+            return (self.crdt, self.clock, None);
+        }
+
+        let ctx = AddCtx {
+            dot: Dot {
+                actor: self.actor,
+                counter: self.first_counter,
+            },
+            prev: self.prev,
+            clock: self.clock.clone(),
+        };
+        let batch = Batch { ops: self.ops, ctx };
+        (self.crdt, self.clock, Some(batch))
+    }
+}
@@ -1,10 +1,16 @@
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::{cmp::Ordering, collections::BTreeMap};
 
-use crate::core::ActorId;
+use crate::core::{ActorId, Dot};
 
 /// A Vector Clock tracks the state of all actors.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+///
+/// Clocks are partially ordered by causality: one clock is greater than another
+/// if it has observed every event the other has, and at least one more. Two
+/// clocks where each has observed an event the other has not are concurrent,
+/// and `partial_cmp` returns `None`. A missing entry is equivalent to a counter
+/// of zero, both for ordering and for equality.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct VClock(pub BTreeMap<ActorId, u64>);
 
 impl VClock {
@@ -20,4 +26,149 @@ impl VClock {
             *self_counter = (*self_counter).max(other_counter);
         }
     }
+
+    /// Returns the latest counter seen from the given actor, or zero if none.
+    pub fn get(&self, actor: &ActorId) -> u64 {
+        self.0.get(actor).cloned().unwrap_or(0)
+    }
+
+    /// Returns `true` if this clock has observed the given dot.
+    pub fn contains(&self, dot: &Dot) -> bool {
+        self.get(&dot.actor) >= dot.counter
+    }
+
+    /// Returns `true` if this clock has observed every event `other` has,
+    /// i.e. `self >= other`.
+    pub fn descends(&self, other: &Self) -> bool {
+        other
+            .0
+            .iter()
+            .all(|(actor, counter)| self.get(actor) >= *counter)
+    }
+
+    /// Returns `true` if this clock has observed every event `other` has, and
+    /// at least one more, i.e. `self > other`.
+    pub fn dominates(&self, other: &Self) -> bool {
+        self.descends(other) && !other.descends(self)
+    }
+
+    /// Returns `true` if neither clock has observed every event of the other.
+    pub fn concurrent_with(&self, other: &Self) -> bool {
+        self.partial_cmp(other).is_none()
+    }
+
+    /// Replaces this clock with the greatest lower bound of itself and `other`,
+    /// taking the minimum of each entry.
+    pub fn glb(&mut self, other: &Self) {
+        self.0.retain(|actor, counter| {
+            *counter = (*counter).min(other.get(actor));
+            *counter > 0
+        });
+    }
+
+    /// Returns the events observed by both clocks, i.e. their greatest lower bound.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut clock = self.clone();
+        clock.glb(other);
+        clock
+    }
+}
+
+impl PartialEq for VClock {
+    fn eq(&self, other: &Self) -> bool {
+        self.descends(other) && other.descends(self)
+    }
+}
+
+impl Eq for VClock {}
+
+impl PartialOrd for VClock {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.descends(other), other.descends(self)) {
+            (true, true) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Greater),
+            (false, true) => Some(Ordering::Less),
+            (false, false) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(entries: &[(u64, u64)]) -> VClock {
+        VClock(
+            entries
+                .iter()
+                .map(|(actor, counter)| (ActorId(*actor), *counter))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_get_and_contains() {
+        let c = clock(&[(1, 3)]);
+
+        assert_eq!(c.get(&ActorId(1)), 3);
+        assert_eq!(c.get(&ActorId(2)), 0);
+        assert!(c.contains(&Dot {
+            actor: ActorId(1),
+            counter: 3
+        }));
+        assert!(!c.contains(&Dot {
+            actor: ActorId(1),
+            counter: 4
+        }));
+        assert!(!c.contains(&Dot {
+            actor: ActorId(2),
+            counter: 1
+        }));
+    }
+
+    #[test]
+    fn test_happened_before() {
+        let earlier = clock(&[(1, 1)]);
+        let later = clock(&[(1, 2), (2, 1)]);
+
+        assert!(later.dominates(&earlier));
+        assert!(later.descends(&earlier));
+        assert!(!earlier.descends(&later));
+        assert!(earlier < later);
+        assert!(!later.concurrent_with(&earlier));
+    }
+
+    #[test]
+    fn test_equal_clocks() {
+        let a = clock(&[(1, 2)]);
+        let b = clock(&[(1, 2), (2, 0)]);
+
+        // A zero entry is the same as a missing one.
+        assert_eq!(a, b);
+        assert_eq!(a.partial_cmp(&b), Some(Ordering::Equal));
+        assert!(a.descends(&b));
+        assert!(!a.dominates(&b));
+    }
+
+    #[test]
+    fn test_concurrent_clocks() {
+        let a = clock(&[(1, 2), (2, 1)]);
+        let b = clock(&[(1, 1), (2, 2)]);
+
+        assert!(a.concurrent_with(&b));
+        assert_eq!(a.partial_cmp(&b), None);
+        assert!(!a.descends(&b));
+        assert!(!b.descends(&a));
+    }
+
+    #[test]
+    fn test_glb_takes_pointwise_minimum() {
+        let mut a = clock(&[(1, 5), (2, 1), (3, 4)]);
+        let b = clock(&[(1, 2), (2, 7)]);
+
+        assert_eq!(a.intersection(&b), clock(&[(1, 2), (2, 1)]));
+
+        a.glb(&b);
+        assert_eq!(a.0, clock(&[(1, 2), (2, 1)]).0);
+    }
 }
@@ -0,0 +1,83 @@
+use crate::flag::{DisableWins, Flag, delegate_flag};
+use serde::{Deserialize, Serialize};
+
+pub use crate::flag::Op;
+
+/// A Disable-Wins Flag, implemented as an op-based `ORSet` of `true` and `false` tokens.
+///
+/// The tokens behave exactly as in an `EWFlag`, but the flag is only enabled when
+/// a `true` token survives and no `false` token does, so a `Disable` wins over
+/// any concurrent `Enable`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DWFlag {
+    flag: Flag<DisableWins>,
+}
+
+delegate_flag!(DWFlag);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+
+    #[test]
+    fn test_initial_value_is_disabled() {
+        let replica = Replica::new(ActorId(1), DWFlag::default());
+        assert!(!replica.read());
+    }
+
+    #[test]
+    fn test_sequential_toggles() {
+        let mut replica = Replica::new(ActorId(1), DWFlag::default());
+
+        replica.apply(Op::Enable);
+        assert!(replica.read());
+
+        replica.apply(Op::Disable);
+        assert!(!replica.read());
+
+        replica.apply(Op::Enable);
+        assert!(replica.read());
+    }
+
+    #[test]
+    fn test_concurrent_disable_wins() {
+        // Arrange: both replicas start from the same disabled flag.
+        let mut replica_a = Replica::new(ActorId(1), DWFlag::default());
+        let mut replica_b = Replica::new(ActorId(2), DWFlag::default());
+        let (op, ctx) = replica_a.apply(Op::Disable);
+        replica_b.apply_remote(op, ctx);
+
+        // Act: A enables the flag while B concurrently disables it again.
+        let (enable, enable_ctx) = replica_a.apply(Op::Enable);
+        let (disable, disable_ctx) = replica_b.apply(Op::Disable);
+        replica_a.apply_remote(disable, disable_ctx);
+        replica_b.apply_remote(enable, enable_ctx);
+
+        // Assert
+        assert!(!replica_a.read());
+        assert_eq!(replica_a.state(), replica_b.state());
+
+        // A later enable that observed both toggles wins.
+        replica_a.apply(Op::Enable);
+        assert!(replica_a.read());
+    }
+
+    #[test]
+    fn test_repeated_disables_keep_one_token() {
+        // Arrange
+        let mut replica_a = Replica::new(ActorId(1), DWFlag::default());
+        let mut replica_b = Replica::new(ActorId(2), DWFlag::default());
+        let ops: Vec<_> = (0..5).map(|_| replica_a.apply(Op::Disable)).collect();
+
+        // Act: B receives the same toggles in reverse order.
+        for (op, ctx) in ops.into_iter().rev() {
+            replica_b.apply_remote(op, ctx);
+        }
+
+        // Assert
+        assert_eq!(replica_a.state().tokens().entries[&false].len(), 1);
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+}
@@ -0,0 +1,64 @@
+use std::fmt;
+
+use crate::{ActorId, Dot};
+
+/// The errors that can occur when applying malformed or inconsistent input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The dot has a counter of zero, which no replica ever generates.
+    ZeroCounter(Dot),
+    /// The dot is not covered by the clock it was sent with.
+    DotNotInClock(Dot),
+    /// The dot does not come after the previous dot of its actor.
+    InvalidPrevious(Dot),
+    /// The dot was already applied with a different operation.
+    ConflictingDot(Dot),
+    /// The operation references a dot that was not observed before it was generated.
+    UnobservedDot(Dot),
+    /// The dot's physical timestamp is further ahead of the local clock than allowed.
+    ClockDrift(Dot),
+    /// The actor does not hold enough rights to perform the operation.
+    InsufficientRights(ActorId),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ZeroCounter(dot) => {
+                write!(f, "dot from actor {} has a zero counter", dot.actor.0)
+            }
+            Error::DotNotInClock(dot) => write!(
+                f,
+                "dot ({}, {}) is not covered by its own clock",
+                dot.actor.0, dot.counter
+            ),
+            Error::InvalidPrevious(dot) => write!(
+                f,
+                "dot ({}, {}) does not follow the previous dot of its actor",
+                dot.actor.0, dot.counter
+            ),
+            Error::ConflictingDot(dot) => write!(
+                f,
+                "dot ({}, {}) was already applied with a different operation",
+                dot.actor.0, dot.counter
+            ),
+            Error::UnobservedDot(dot) => write!(
+                f,
+                "operation references dot ({}, {}) outside of its causal context",
+                dot.actor.0, dot.counter
+            ),
+            Error::ClockDrift(dot) => write!(
+                f,
+                "dot ({}, {}) is too far ahead of the local physical clock",
+                dot.actor.0, dot.counter
+            ),
+            Error::InsufficientRights(actor) => write!(
+                f,
+                "actor {} does not hold enough rights for this operation",
+                actor.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,93 @@
+use crate::flag::{EnableWins, Flag, delegate_flag};
+use serde::{Deserialize, Serialize};
+
+pub use crate::flag::Op;
+
+/// An Enable-Wins Flag, implemented as an op-based `ORSet` of `true` and `false` tokens.
+///
+/// An `Enable` adds a `true` token tagged with its dot and removes every token
+/// it has observed, and a `Disable` does the same with a `false` token, so
+/// repeated toggles keep a single token. Concurrent toggles both leave a token,
+/// and the flag is enabled whenever a `true` token survives.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EWFlag {
+    flag: Flag<EnableWins>,
+}
+
+delegate_flag!(EWFlag);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+
+    #[test]
+    fn test_initial_value_is_disabled() {
+        let replica = Replica::new(ActorId(1), EWFlag::default());
+        assert!(!replica.read());
+    }
+
+    #[test]
+    fn test_sequential_toggles() {
+        let mut replica = Replica::new(ActorId(1), EWFlag::default());
+
+        replica.apply(Op::Enable);
+        assert!(replica.read());
+
+        replica.apply(Op::Disable);
+        assert!(!replica.read());
+
+        replica.apply(Op::Enable);
+        assert!(replica.read());
+    }
+
+    #[test]
+    fn test_concurrent_enable_wins() {
+        // Arrange: both replicas start from the same enabled flag.
+        let mut replica_a = Replica::new(ActorId(1), EWFlag::default());
+        let mut replica_b = Replica::new(ActorId(2), EWFlag::default());
+        let (op, ctx) = replica_a.apply(Op::Enable);
+        replica_b.apply_remote(op, ctx);
+
+        // Act: A disables the flag while B concurrently enables it again.
+        let (disable, disable_ctx) = replica_a.apply(Op::Disable);
+        let (enable, enable_ctx) = replica_b.apply(Op::Enable);
+        replica_a.apply_remote(enable, enable_ctx);
+        replica_b.apply_remote(disable, disable_ctx);
+
+        // Assert
+        assert!(replica_a.read());
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+
+    #[test]
+    fn test_disable_delivered_before_observed_enable() {
+        let mut replica_a = Replica::new(ActorId(1), EWFlag::default());
+        let enable = replica_a.apply(Op::Enable);
+        let disable = replica_a.apply(Op::Disable);
+
+        let mut replica_b = Replica::new(ActorId(2), EWFlag::default());
+        replica_b.apply_remote(disable.0, disable.1);
+        replica_b.apply_remote(enable.0, enable.1);
+
+        assert!(!replica_b.read());
+    }
+
+    #[test]
+    fn test_repeated_enables_keep_one_token() {
+        // Arrange
+        let mut replica_a = Replica::new(ActorId(1), EWFlag::default());
+        let mut replica_b = Replica::new(ActorId(2), EWFlag::default());
+        let ops: Vec<_> = (0..5).map(|_| replica_a.apply(Op::Enable)).collect();
+
+        // Act: B receives the same toggles in reverse order.
+        for (op, ctx) in ops.into_iter().rev() {
+            replica_b.apply_remote(op, ctx);
+        }
+
+        // Assert
+        assert_eq!(replica_a.state().tokens().entries[&true].len(), 1);
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+}
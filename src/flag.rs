@@ -0,0 +1,140 @@
+use crate::{
+    VClock,
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal},
+    or_set::{self, ORSet},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+/// Operations for an `EWFlag` or a `DWFlag` can enable or disable it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op {
+    Enable,
+    Disable,
+}
+
+/// Decides whether a flag is enabled from the tokens that survive.
+pub trait Bias: Debug + Clone + PartialEq + Eq + Default {
+    fn read(tokens: &ORSet<bool>) -> bool;
+}
+
+/// The flag is enabled whenever a `true` token survives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EnableWins;
+
+impl Bias for EnableWins {
+    fn read(tokens: &ORSet<bool>) -> bool {
+        tokens.contains(&true)
+    }
+}
+
+/// The flag is only enabled when a `true` token survives and no `false` one does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DisableWins;
+
+impl Bias for DisableWins {
+    fn read(tokens: &ORSet<bool>) -> bool {
+        tokens.contains(&true) && !tokens.contains(&false)
+    }
+}
+
+/// The state shared by both flags: an op-based `ORSet` of `true` and `false`
+/// tokens.
+///
+/// An `Enable` adds a `true` token tagged with its dot and removes every token
+/// it has observed, and a `Disable` does the same with a `false` token, so
+/// repeated toggles keep a single token. Concurrent toggles both leave a token,
+/// and the bias decides which of them wins.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Flag<B> {
+    pub tokens: ORSet<bool>,
+    #[serde(skip)]
+    bias: PhantomData<B>,
+}
+
+impl<B: Bias> CmRDT for Flag<B> {
+    type Op = Op;
+    type Value = bool;
+
+    fn apply(&mut self, op: Self::Op, ctx: AddCtx) {
+        let enabled = op == Op::Enable;
+        let observed = AddCtx {
+            clock: ctx.observed(),
+            ..ctx.clone()
+        };
+        self.tokens.apply(or_set::Op::Rm(!enabled), ctx.clone());
+        self.tokens.apply(or_set::Op::Rm(enabled), observed);
+        self.tokens.apply(or_set::Op::Add(enabled), ctx);
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.tokens.merge(other.tokens);
+    }
+
+    fn read(&self) -> Self::Value {
+        B::read(&self.tokens)
+    }
+
+    fn purge(&mut self, stable: &VClock) {
+        self.tokens.purge(stable);
+    }
+}
+
+impl<B: Bias> InfallibleLocal for Flag<B> {}
+
+impl<B: Bias> DeltaCmRDT for Flag<B> {
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        Self {
+            tokens: self.tokens.delta_since(applied),
+            bias: PhantomData,
+        }
+    }
+}
+
+/// Implements the CRDT traits of a public flag type by delegating to its
+/// `flag` field.
+macro_rules! delegate_flag {
+    ($flag:ident) => {
+        impl $flag {
+            /// Returns the surviving `true` and `false` tokens.
+            pub fn tokens(&self) -> &$crate::or_set::ORSet<bool> {
+                &self.flag.tokens
+            }
+        }
+
+        impl $crate::core::CmRDT for $flag {
+            type Op = $crate::flag::Op;
+            type Value = bool;
+
+            fn apply(&mut self, op: Self::Op, ctx: $crate::core::AddCtx) {
+                self.flag.apply(op, ctx);
+            }
+
+            fn merge(&mut self, other: Self) {
+                self.flag.merge(other.flag);
+            }
+
+            fn read(&self) -> Self::Value {
+                self.flag.read()
+            }
+
+            fn purge(&mut self, stable: &$crate::VClock) {
+                self.flag.purge(stable);
+            }
+        }
+
+        impl $crate::core::InfallibleLocal for $flag {}
+
+        impl $crate::core::DeltaCmRDT for $flag {
+            fn delta_since(&self, applied: &$crate::core::AppliedDots) -> Self {
+                Self {
+                    flag: self.flag.delta_since(applied),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use delegate_flag;
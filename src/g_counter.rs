@@ -1,6 +1,6 @@
 use crate::{
-    Dot,
-    core::{AddCtx, CmRDT},
+    ActorId, Dot, Error, VClock,
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal},
 };
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -11,45 +11,151 @@ use std::collections::BTreeMap;
 /// this implementation stores a log of every individual `Inc` operation. Each
 /// operation is uniquely identified by its `Dot`, ensuring that all increments
 /// are preserved when replica logs are merged.
+///
+/// ## Compaction
+/// To keep the log from growing without bound, [`GCounter::compact`] folds every
+/// increment covered by a causally stable clock into a running total per actor.
+/// The folded prefix is remembered in `compacted`, so a re-delivered increment
+/// from that prefix is still recognised and ignored.
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct GCounter {
+    #[serde(with = "crate::core::serde_pairs")]
     pub ops: BTreeMap<Dot, u64>,
+    /// The dots that have been folded into `totals`, per actor.
+    pub compacted: VClock,
+    /// The sum of every folded increment, per actor.
+    pub totals: BTreeMap<ActorId, u64>,
 }
 
 /// The only operation for a GCounter is to increment its value.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Op {
     Inc(u64),
 }
 
+impl GCounter {
+    /// Returns the sum of every increment made by the given actor.
+    pub fn actor_value(&self, actor: &ActorId) -> u64 {
+        let folded = self.totals.get(actor).cloned().unwrap_or(0);
+        let logged: u64 = self
+            .ops
+            .iter()
+            .filter(|(dot, _)| dot.actor == *actor)
+            .map(|(_, amount)| amount)
+            .sum();
+        folded + logged
+    }
+
+    /// Folds every increment covered by the given clock into its actor's total.
+    ///
+    /// The clock must be causally stable: every replica must already have
+    /// applied every event it covers. Otherwise, an increment that has not
+    /// arrived yet would be ignored as a duplicate once it does.
+    pub fn compact(&mut self, stable: &VClock) {
+        let (folded, kept): (BTreeMap<Dot, u64>, BTreeMap<Dot, u64>) =
+            std::mem::take(&mut self.ops)
+                .into_iter()
+                .partition(|(dot, _)| stable.contains(dot));
+        self.ops = kept;
+
+        for (dot, amount) in folded {
+            *self.totals.entry(dot.actor).or_insert(0) += amount;
+        }
+        for (actor, counter) in &stable.0 {
+            if *counter > self.compacted.get(actor) {
+                self.compacted.0.insert(*actor, *counter);
+            }
+        }
+    }
+}
+
 impl CmRDT for GCounter {
     type Op = Op;
     type Value = u64;
 
-    /// Records an operation, identified by its dot.
+    /// Records an operation, identified by its dot, unless it was already folded.
     fn apply(&mut self, op: Self::Op, ctx: AddCtx) {
         match op {
             Op::Inc(amount) => {
-                self.ops.insert(ctx.dot, amount);
+                if !self.compacted.contains(&ctx.dot) {
+                    self.ops.insert(ctx.dot, amount);
+                }
             }
         }
     }
 
     fn merge(&mut self, other: Self) {
+        // For each actor, the side that folded further has a total covering
+        // everything the other side folded.
+        for (actor, counter) in other.compacted.0 {
+            if counter > self.compacted.get(&actor) {
+                self.compacted.0.insert(actor, counter);
+                let total = other.totals.get(&actor).cloned().unwrap_or(0);
+                self.totals.insert(actor, total);
+            }
+        }
+
         // `extend` will overwrite our ops with the other's if the keys (Dots) are the same,
         // which is fine since the operation (dot -> amount) is identical.
         self.ops.extend(other.ops);
+        let compacted = &self.compacted;
+        self.ops.retain(|dot, _| !compacted.contains(dot));
     }
 
     fn read(&self) -> Self::Value {
-        self.ops.values().sum()
+        self.ops.values().sum::<u64>() + self.totals.values().sum::<u64>()
+    }
+
+    /// Folds the stable increments, see [`GCounter::compact`].
+    fn purge(&mut self, stable: &VClock) {
+        self.compact(stable);
+    }
+
+    /// Rejects an increment whose dot was already recorded with a different amount.
+    fn validate(&self, op: &Self::Op, ctx: &AddCtx) -> Result<(), Error> {
+        let Op::Inc(amount) = op;
+
+        match self.ops.get(&ctx.dot) {
+            Some(existing) if existing != amount => Err(Error::ConflictingDot(ctx.dot)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl InfallibleLocal for GCounter {}
+
+impl DeltaCmRDT for GCounter {
+    /// Returns the increments whose dots the peer has not applied, along with
+    /// the totals of any folded prefix it has not fully applied.
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        let mut delta = Self {
+            ops: self
+                .ops
+                .iter()
+                .filter(|(dot, _)| !applied.contains(dot))
+                .map(|(dot, amount)| (*dot, *amount))
+                .collect(),
+            ..Self::default()
+        };
+        for (actor, counter) in &self.compacted.0 {
+            let folded = Dot {
+                actor: *actor,
+                counter: *counter,
+            };
+            if !applied.contains_up_to(&folded) {
+                delta.compacted.0.insert(*actor, *counter);
+                let total = self.totals.get(actor).cloned().unwrap_or(0);
+                delta.totals.insert(*actor, total);
+            }
+        }
+        delta
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{ActorId, Replica};
+    use crate::core::Replica;
 
     #[test]
     fn test_initial_value_is_zero() {
@@ -127,4 +233,64 @@ mod tests {
         // Assert: the value should not change
         assert_eq!(replica_a.read(), value_before_merge);
     }
+
+    #[test]
+    fn test_try_apply_rejects_conflicting_dot() {
+        // Arrange
+        let mut replica = Replica::new(ActorId(1), GCounter::default());
+        let (_, ctx) = replica.apply(Op::Inc(5));
+
+        // Act: the same dot arrives again, but with a different amount.
+        let mut counter = replica.state().clone();
+        let conflicting = counter.try_apply(Op::Inc(6), ctx.clone());
+        let redelivered = counter.try_apply(Op::Inc(5), ctx.clone());
+
+        // Assert
+        assert_eq!(conflicting, Err(Error::ConflictingDot(ctx.dot)));
+        assert_eq!(redelivered, Ok(()));
+        assert_eq!(counter.read(), 5);
+    }
+
+    #[test]
+    fn test_delta_since_contains_only_unseen_dots() {
+        // Arrange: B has seen A's first increment, but not the second.
+        let mut replica_a = Replica::new(ActorId(1), GCounter::default());
+        let mut replica_b = Replica::new(ActorId(2), GCounter::default());
+
+        let (op, ctx) = replica_a.apply(Op::Inc(5));
+        replica_b.apply_remote(op, ctx);
+        replica_a.apply(Op::Inc(7));
+
+        // Act
+        let (delta, clock) = replica_a.delta_since_applied(replica_b.applied());
+        replica_b.merge_delta(delta.clone(), clock);
+
+        // Assert
+        assert_eq!(delta.ops.len(), 1);
+        assert_eq!(delta.read(), 7);
+        assert_eq!(replica_b.state(), replica_a.state());
+    }
+
+    #[test]
+    fn test_compact_folds_stable_ops() {
+        // Arrange
+        let mut replica = Replica::new(ActorId(1), GCounter::default());
+        let (op, first_ctx) = replica.apply(Op::Inc(5));
+        replica.apply(Op::Inc(10));
+        let stable = replica.clock().clone();
+        replica.apply(Op::Inc(1));
+
+        // Act
+        let mut counter = replica.state().clone();
+        counter.compact(&stable);
+
+        // Assert: the two stable increments are folded into a single total.
+        assert_eq!(counter.ops.len(), 1);
+        assert_eq!(counter.totals[&ActorId(1)], 15);
+        assert_eq!(counter.read(), 16);
+
+        // A folded increment that is delivered again is still ignored.
+        counter.apply(op, first_ctx);
+        assert_eq!(counter.read(), 16);
+    }
 }
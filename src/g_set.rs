@@ -1,4 +1,4 @@
-use crate::core::{AddCtx, CmRDT};
+use crate::core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal};
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeSet, fmt::Debug};
 
@@ -13,7 +13,7 @@ pub struct GSet<T: Clone + Ord> {
 }
 
 /// The only operation for a GSet is to add a value.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Op<T> {
     Add(T),
 }
@@ -37,6 +37,16 @@ impl<T: Clone + Ord> CmRDT for GSet<T> {
     }
 }
 
+impl<T: Clone + Ord> InfallibleLocal for GSet<T> {}
+
+impl<T: Clone + Ord> DeltaCmRDT for GSet<T> {
+    /// A GSet does not record which op added each value, so the delta is
+    /// always the full set.
+    fn delta_since(&self, _applied: &AppliedDots) -> Self {
+        self.clone()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,118 @@
+use crate::core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal, Lattice};
+use serde::{Deserialize, Serialize};
+
+/// An operation-based register over any join-semilattice (CmRDT).
+///
+/// Every write is joined into the current state with [`Lattice::join`], so any
+/// user-defined semilattice can be replicated through a `Replica`. The register
+/// starts out empty, which acts as the bottom of the lattice.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LatticeRegister<L> {
+    /// The join of every value written so far, if any.
+    pub value: Option<L>,
+}
+
+/// The only operation for a LatticeRegister is to join a value into it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<L> {
+    Join(L),
+}
+
+impl<L> Default for LatticeRegister<L> {
+    fn default() -> Self {
+        Self { value: None }
+    }
+}
+
+impl<L: Lattice> Lattice for LatticeRegister<L> {
+    fn join(&mut self, other: Self) {
+        let Some(other) = other.value else {
+            return;
+        };
+        match &mut self.value {
+            Some(value) => value.join(other),
+            None => self.value = Some(other),
+        }
+    }
+}
+
+impl<L: Lattice + Clone> CmRDT for LatticeRegister<L> {
+    type Op = Op<L>;
+    type Value = Option<L>;
+
+    fn apply(&mut self, op: Self::Op, _ctx: AddCtx) {
+        let Op::Join(value) = op;
+        self.join(Self { value: Some(value) });
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.join(other);
+    }
+
+    fn read(&self) -> Self::Value {
+        self.value.clone()
+    }
+}
+
+impl<L: Lattice + Clone> InfallibleLocal for LatticeRegister<L> {}
+
+impl<L: Lattice + Clone> DeltaCmRDT for LatticeRegister<L> {
+    /// A LatticeRegister does not record which ops were joined into it, so the
+    /// delta is always the full register.
+    fn delta_since(&self, _applied: &AppliedDots) -> Self {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+    use std::collections::BTreeMap;
+
+    /// The highest version seen for each shard, joined by pointwise maximum.
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    struct HighWaterMarks(BTreeMap<&'static str, u64>);
+
+    impl Lattice for HighWaterMarks {
+        fn join(&mut self, other: Self) {
+            for (shard, mark) in other.0 {
+                let current = self.0.entry(shard).or_default();
+                *current = (*current).max(mark);
+            }
+        }
+    }
+
+    fn marks(entries: &[(&'static str, u64)]) -> HighWaterMarks {
+        HighWaterMarks(entries.iter().cloned().collect())
+    }
+
+    #[test]
+    fn test_initial_value_is_none() {
+        let replica = Replica::new(ActorId(1), LatticeRegister::<HighWaterMarks>::default());
+        assert_eq!(replica.read(), None);
+    }
+
+    #[test]
+    fn test_writes_are_joined() {
+        let mut replica = Replica::new(ActorId(1), LatticeRegister::default());
+        replica.apply(Op::Join(marks(&[("a", 3), ("b", 1)])));
+        replica.apply(Op::Join(marks(&[("a", 2), ("c", 4)])));
+
+        assert_eq!(replica.read(), Some(marks(&[("a", 3), ("b", 1), ("c", 4)])));
+    }
+
+    #[test]
+    fn test_concurrent_writes_converge() {
+        let mut replica_a = Replica::new(ActorId(1), LatticeRegister::default());
+        let mut replica_b = Replica::new(ActorId(2), LatticeRegister::default());
+
+        let (op_a, ctx_a) = replica_a.apply(Op::Join(marks(&[("a", 5)])));
+        let (op_b, ctx_b) = replica_b.apply(Op::Join(marks(&[("a", 1), ("b", 2)])));
+        replica_a.apply_remote(op_b, ctx_b);
+        replica_b.apply_remote(op_a, ctx_a);
+
+        assert_eq!(replica_a.state(), replica_b.state());
+        assert_eq!(replica_a.read(), Some(marks(&[("a", 5), ("b", 2)])));
+    }
+}
@@ -1,11 +1,48 @@
+pub mod bounded_counter;
+pub mod codec;
 pub mod core;
+pub mod dw_flag;
+pub mod error;
+pub mod ew_flag;
+mod flag;
 pub mod g_counter;
 pub mod g_set;
+pub mod lattice_register;
+pub mod lww_element_set;
+pub mod lww_map;
 pub mod lww_register;
+pub mod max_register;
+pub mod min_register;
+pub mod mv_register;
+pub mod or_map;
+pub mod or_set;
 pub mod pn_counter;
+pub mod reset_counter;
+pub mod rga;
+pub mod storage;
+pub mod sync;
+pub mod two_p_set;
 
 // Public API
-pub use core::{ActorId, AddCtx, CmRDT, Dot, ReadCtx, VClock};
+pub use bounded_counter::BoundedCounter;
+pub use core::{
+    ActorId, AddCtx, ApplyOutcome, CmRDT, DeltaCmRDT, Dot, Envelope, InfallibleLocal, Lattice,
+    ReadCtx, VClock,
+};
+pub use dw_flag::DWFlag;
+pub use error::Error;
+pub use ew_flag::EWFlag;
 pub use g_counter::GCounter;
 pub use g_set::GSet;
+pub use lattice_register::LatticeRegister;
+pub use lww_element_set::LWWElementSet;
+pub use lww_map::LWWMap;
+pub use max_register::MaxRegister;
+pub use min_register::MinRegister;
+pub use mv_register::MVRegister;
+pub use or_map::ORMap;
+pub use or_set::ORSet;
 pub use pn_counter::PNCounter;
+pub use reset_counter::ResetCounter;
+pub use rga::Rga;
+pub use two_p_set::TwoPSet;
@@ -0,0 +1,233 @@
+use crate::{
+    Dot, Error, VClock,
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An operation-based, Last-Write-Wins Element Set (CmRDT).
+///
+/// For each value, the set remembers the greatest `Dot` of an `Add` and of a `Rm`.
+/// The value is in the set when its latest add is greater than its latest remove,
+/// so of two concurrent operations on the same value, the one with the larger dot
+/// wins, and a value can be added again after it was removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LWWElementSet<T: Clone + Ord> {
+    /// The latest add and remove of every value that was ever added or removed.
+    pub elements: BTreeMap<T, Timestamps>,
+}
+
+/// The dots of the latest add and remove of a value in an `LWWElementSet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Timestamps {
+    pub add: Option<Dot>,
+    pub rm: Option<Dot>,
+}
+
+/// Operations for an LWWElementSet can add or remove a value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<T> {
+    Add(T),
+    Rm(T),
+}
+
+impl<T: Clone + Ord> Default for LWWElementSet<T> {
+    fn default() -> Self {
+        Self {
+            elements: BTreeMap::new(),
+        }
+    }
+}
+
+impl Timestamps {
+    /// Returns `true` if the latest add is greater than the latest remove.
+    pub fn is_present(&self) -> bool {
+        self.add > self.rm
+    }
+
+    /// Keeps the greater dot of each kind.
+    fn merge(&mut self, other: Self) {
+        self.add = self.add.max(other.add);
+        self.rm = self.rm.max(other.rm);
+    }
+}
+
+impl<T: Clone + Ord> LWWElementSet<T> {
+    /// Returns `true` if the value is currently in the set.
+    pub fn contains(&self, value: &T) -> bool {
+        self.elements
+            .get(value)
+            .is_some_and(|timestamps| timestamps.is_present())
+    }
+}
+
+impl<T: Clone + Ord> CmRDT for LWWElementSet<T> {
+    type Op = Op<T>;
+    type Value = BTreeSet<T>;
+
+    /// Records the op's dot as the latest add or remove of the value, if it is greater.
+    fn apply(&mut self, op: Self::Op, ctx: AddCtx) {
+        let (value, timestamps) = match op {
+            Op::Add(value) => (
+                value,
+                Timestamps {
+                    add: Some(ctx.dot),
+                    rm: None,
+                },
+            ),
+            Op::Rm(value) => (
+                value,
+                Timestamps {
+                    add: None,
+                    rm: Some(ctx.dot),
+                },
+            ),
+        };
+        self.elements.entry(value).or_default().merge(timestamps);
+    }
+
+    fn merge(&mut self, other: Self) {
+        for (value, timestamps) in other.elements {
+            self.elements.entry(value).or_default().merge(timestamps);
+        }
+    }
+
+    fn read(&self) -> Self::Value {
+        self.elements
+            .iter()
+            .filter(|(_, timestamps)| timestamps.is_present())
+            .map(|(value, _)| value.clone())
+            .collect()
+    }
+
+    /// Drops the values whose winning remove is causally stable.
+    ///
+    /// This is only safe under the causal delivery that the stable frontier
+    /// assumes (see [`Replica`](crate::core::Replica)): every peer has then
+    /// sent us each add it issued before observing the remove, so any add that
+    /// could still arrive observed the remove and has a greater dot. An add
+    /// concurrent with the remove that is still in flight, for example because
+    /// a peer's clock was learned from a state merge, may have a smaller dot
+    /// and brings the value back once the tombstone is gone.
+    fn purge(&mut self, stable: &VClock) {
+        self.elements.retain(|_, timestamps| {
+            timestamps.is_present() || !timestamps.rm.is_some_and(|dot| stable.contains(&dot))
+        });
+    }
+
+    /// Rejects an op whose dot was already recorded for the value with the
+    /// opposite kind of operation.
+    fn validate(&self, op: &Self::Op, ctx: &AddCtx) -> Result<(), Error> {
+        let (value, opposite): (_, fn(&Timestamps) -> Option<Dot>) = match op {
+            Op::Add(value) => (value, |timestamps| timestamps.rm),
+            Op::Rm(value) => (value, |timestamps| timestamps.add),
+        };
+
+        match self.elements.get(value) {
+            Some(timestamps) if opposite(timestamps) == Some(ctx.dot) => {
+                Err(Error::ConflictingDot(ctx.dot))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<T: Clone + Ord> InfallibleLocal for LWWElementSet<T> {}
+
+impl<T: Clone + Ord> DeltaCmRDT for LWWElementSet<T> {
+    /// Returns the values whose latest add or remove the peer has not applied.
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        let unseen = |dot: Option<Dot>| dot.is_some_and(|dot| !applied.contains(&dot));
+        Self {
+            elements: self
+                .elements
+                .iter()
+                .filter(|(_, timestamps)| unseen(timestamps.add) || unseen(timestamps.rm))
+                .map(|(value, timestamps)| (value.clone(), *timestamps))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+
+    #[test]
+    fn test_initial_value_is_empty() {
+        let replica = Replica::new(ActorId(1), LWWElementSet::<i32>::default());
+        assert!(replica.read().is_empty());
+    }
+
+    #[test]
+    fn test_add_remove_and_re_add() {
+        let mut replica = Replica::new(ActorId(1), LWWElementSet::default());
+
+        replica.apply(Op::Add("token"));
+        replica.apply(Op::Rm("token"));
+        assert!(!replica.state().contains(&"token"));
+
+        replica.apply(Op::Add("token"));
+        assert_eq!(replica.read(), BTreeSet::from(["token"]));
+    }
+
+    #[test]
+    fn test_larger_dot_wins_on_concurrent_ops() {
+        // Arrange: both replicas observe the same add.
+        let mut replica_a = Replica::new(ActorId(1), LWWElementSet::default());
+        let mut replica_b = Replica::new(ActorId(2), LWWElementSet::default());
+        let (op, ctx) = replica_a.apply(Op::Add(7));
+        replica_b.apply_remote(op, ctx);
+
+        // Act: A adds again while B concurrently removes, with tied counters.
+        let (add, add_ctx) = replica_a.apply(Op::Add(7));
+        let (rm, rm_ctx) = replica_b.apply(Op::Rm(7));
+        replica_a.apply_remote(rm, rm_ctx);
+        replica_b.apply_remote(add, add_ctx);
+
+        // Assert: the remove from the greater actor wins.
+        assert!(replica_a.read().is_empty());
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+
+    #[test]
+    fn test_remove_delivered_before_older_add() {
+        let mut replica_a = Replica::new(ActorId(1), LWWElementSet::default());
+        let add = replica_a.apply(Op::Add(3));
+        let rm = replica_a.apply(Op::Rm(3));
+
+        let mut replica_b = Replica::new(ActorId(2), LWWElementSet::default());
+        replica_b.apply_remote(rm.0, rm.1);
+        replica_b.apply_remote(add.0, add.1);
+
+        assert!(replica_b.read().is_empty());
+    }
+
+    #[test]
+    fn test_purge_drops_stable_removals() {
+        let mut replica = Replica::new(ActorId(1), LWWElementSet::default());
+        replica.apply(Op::Add(1));
+        replica.apply(Op::Add(2));
+        replica.apply(Op::Rm(2));
+        replica.observe_peer(ActorId(2), replica.clock().clone());
+
+        replica.purge();
+
+        assert_eq!(replica.state().elements.len(), 1);
+        assert_eq!(replica.read(), BTreeSet::from([1]));
+    }
+
+    #[test]
+    fn test_try_apply_rejects_conflicting_dot() {
+        let mut replica = Replica::new(ActorId(1), LWWElementSet::default());
+        let (_, ctx) = replica.apply(Op::Add(1));
+
+        let mut set = replica.state().clone();
+        assert_eq!(
+            set.try_apply(Op::Rm(1), ctx.clone()),
+            Err(Error::ConflictingDot(ctx.dot))
+        );
+        assert_eq!(set.try_apply(Op::Add(1), ctx), Ok(()));
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{
+    Dot, Error, VClock,
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// An operation-based, Last-Write-Wins Map (CmRDT).
+///
+/// Every key behaves like its own `LWWRegister`: the `Set` or `Remove` with the
+/// greatest `Dot` wins. A `Remove` leaves a tombstone carrying its dot, so a
+/// `Set` with a smaller dot that is delivered later is still discarded, while
+/// any later `Set` brings the key back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LWWMap<K: Clone + Ord, V: Clone> {
+    /// The latest write for each key, including removes.
+    pub entries: BTreeMap<K, Entry<V>>,
+}
+
+/// The latest write to a key of an `LWWMap`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry<V> {
+    /// The dot of the operation that wrote this entry.
+    pub dot: Dot,
+    /// The value of the key, or `None` if it was removed.
+    pub value: Option<V>,
+}
+
+/// Operations for an LWWMap can set or remove the value of a key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<K, V> {
+    Set(K, V),
+    Remove(K),
+}
+
+impl<K: Clone + Ord, V: Clone> Default for LWWMap<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K: Clone + Ord, V: Clone> LWWMap<K, V> {
+    /// Returns the value of the key, if it is live.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).and_then(|entry| entry.value.as_ref())
+    }
+
+    /// Returns `true` if the key has a live value.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Iterates over the live entries in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries
+            .iter()
+            .filter_map(|(key, entry)| entry.value.as_ref().map(|value| (key, value)))
+    }
+
+    /// Keeps the entry with the greater dot.
+    fn write(&mut self, key: K, entry: Entry<V>) {
+        match self.entries.get(&key) {
+            Some(current) if current.dot >= entry.dot => {}
+            _ => {
+                self.entries.insert(key, entry);
+            }
+        }
+    }
+}
+
+impl<K: Clone + Ord, V: Clone + PartialEq> CmRDT for LWWMap<K, V> {
+    type Op = Op<K, V>;
+    type Value = BTreeMap<K, V>;
+
+    /// Writes the key if the op's dot is greater than the dot of its current entry.
+    fn apply(&mut self, op: Self::Op, ctx: AddCtx) {
+        let (key, value) = match op {
+            Op::Set(key, value) => (key, Some(value)),
+            Op::Remove(key) => (key, None),
+        };
+        self.write(
+            key,
+            Entry {
+                dot: ctx.dot,
+                value,
+            },
+        );
+    }
+
+    /// Keeps the entry with the greater dot for every key.
+    fn merge(&mut self, other: Self) {
+        for (key, entry) in other.entries {
+            self.write(key, entry);
+        }
+    }
+
+    /// Reads the live entries.
+    fn read(&self) -> Self::Value {
+        self.iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Drops the tombstones of removes that are causally stable. As for an
+    /// `LWWElementSet`, this relies on causal delivery: only then has every
+    /// write the remove could win against already been delivered.
+    fn purge(&mut self, stable: &VClock) {
+        self.entries
+            .retain(|_, entry| entry.value.is_some() || !stable.contains(&entry.dot));
+    }
+
+    /// Rejects an op whose dot already wrote something different to the key.
+    fn validate(&self, op: &Self::Op, ctx: &AddCtx) -> Result<(), Error> {
+        let (key, value) = match op {
+            Op::Set(key, value) => (key, Some(value)),
+            Op::Remove(key) => (key, None),
+        };
+
+        match self.entries.get(key) {
+            Some(entry) if entry.dot == ctx.dot && entry.value.as_ref() != value => {
+                Err(Error::ConflictingDot(ctx.dot))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<K: Clone + Ord, V: Clone + PartialEq> InfallibleLocal for LWWMap<K, V> {}
+
+impl<K: Clone + Ord, V: Clone + PartialEq> DeltaCmRDT for LWWMap<K, V> {
+    /// Returns the entries whose writes the peer has not applied.
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        Self {
+            entries: self
+                .entries
+                .iter()
+                .filter(|(_, entry)| !applied.contains(&entry.dot))
+                .map(|(key, entry)| (key.clone(), entry.clone()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+
+    #[test]
+    fn test_initial_value_is_empty() {
+        let replica = Replica::new(ActorId(1), LWWMap::<String, i32>::default());
+        assert!(replica.read().is_empty());
+    }
+
+    #[test]
+    fn test_set_and_remove() {
+        // Arrange
+        let mut replica = Replica::new(ActorId(1), LWWMap::default());
+
+        // Act
+        replica.apply(Op::Set("a", 1));
+        replica.apply(Op::Set("b", 2));
+        replica.apply(Op::Set("a", 3));
+        replica.apply(Op::Remove("b"));
+
+        // Assert
+        assert_eq!(replica.read(), BTreeMap::from([("a", 3)]));
+        assert_eq!(replica.state().get(&"a"), Some(&3));
+        assert!(!replica.state().contains_key(&"b"));
+        assert_eq!(replica.state().iter().count(), 1);
+    }
+
+    #[test]
+    fn test_later_set_wins_over_remove() {
+        let mut replica_a = Replica::new(ActorId(1), LWWMap::default());
+        let mut replica_b = Replica::new(ActorId(2), LWWMap::default());
+
+        let (op, ctx) = replica_a.apply(Op::Set("k", 1));
+        replica_b.apply_remote(op, ctx);
+
+        // B removes the key, then A sets it again after observing the remove.
+        let (rm_op, rm_ctx) = replica_b.apply(Op::Remove("k"));
+        replica_a.apply_remote(rm_op, rm_ctx);
+        let (set_op, set_ctx) = replica_a.apply(Op::Set("k", 2));
+        replica_b.apply_remote(set_op, set_ctx);
+
+        assert_eq!(replica_a.read(), BTreeMap::from([("k", 2)]));
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+
+    #[test]
+    fn test_remove_tombstone_discards_older_set() {
+        // Arrange
+        let mut replica_a = Replica::new(ActorId(1), LWWMap::default());
+        let mut replica_b = Replica::new(ActorId(2), LWWMap::default());
+
+        let (set_op, set_ctx) = replica_a.apply(Op::Set("k", 1));
+        let (rm_op, rm_ctx) = replica_a.apply(Op::Remove("k"));
+
+        // Act: B receives the remove before the set it overrides.
+        replica_b.apply_remote(rm_op, rm_ctx);
+        replica_b.apply_remote(set_op, set_ctx);
+
+        // Assert
+        assert!(replica_b.read().is_empty());
+        assert_eq!(replica_b.state().entries.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_is_commutative() {
+        let mut replica_a = Replica::new(ActorId(1), LWWMap::default());
+        replica_a.apply(Op::Set(1, 'a'));
+        replica_a.apply(Op::Set(2, 'b'));
+
+        let mut replica_b = Replica::new(ActorId(2), LWWMap::default());
+        replica_b.apply(Op::Set(1, 'x'));
+        replica_b.apply(Op::Remove(2));
+        replica_b.apply(Op::Remove(2));
+
+        let mut merged_ab = replica_a.clone();
+        merged_ab.merge(replica_b.state().clone(), replica_b.clock().clone());
+
+        let mut merged_ba = replica_b.clone();
+        merged_ba.merge(replica_a.state().clone(), replica_a.clock().clone());
+
+        assert_eq!(merged_ab.state(), merged_ba.state());
+        assert_eq!(merged_ab.read(), BTreeMap::from([(1, 'x')]));
+    }
+
+    #[test]
+    fn test_try_apply_rejects_conflicting_dot() {
+        let mut replica = Replica::new(ActorId(1), LWWMap::default());
+        let (_, ctx) = replica.apply(Op::Set("k", 1));
+
+        let mut map = replica.state().clone();
+        assert_eq!(
+            map.try_apply(Op::Remove("k"), ctx.clone()),
+            Err(Error::ConflictingDot(ctx.dot))
+        );
+        assert_eq!(map.try_apply(Op::Set("k", 1), ctx), Ok(()));
+    }
+
+    #[test]
+    fn test_purge_drops_stable_tombstones() {
+        let mut replica = Replica::new(ActorId(1), LWWMap::default());
+        replica.apply(Op::Set("a", 1));
+        replica.apply(Op::Set("b", 2));
+        replica.apply(Op::Remove("b"));
+        replica.observe_peer(ActorId(2), replica.clock().clone());
+
+        replica.purge();
+
+        assert_eq!(replica.state().entries.len(), 1);
+        assert_eq!(replica.read(), BTreeMap::from([("a", 1)]));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    Dot,
-    core::{AddCtx, CmRDT},
+    Dot, Error,
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal},
 };
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
@@ -21,7 +21,7 @@ pub struct LWWRegister<T: Clone> {
 }
 
 /// The only operation for a LWWRegister is to set its value.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Op<T> {
     Set(T),
 }
@@ -70,6 +70,32 @@ impl<T: Clone + Debug + PartialEq> CmRDT for LWWRegister<T> {
     fn read(&self) -> Self::Value {
         self.value.clone()
     }
+
+    /// Rejects a `Set` whose dot already set the register to a different value.
+    fn validate(&self, op: &Self::Op, ctx: &AddCtx) -> Result<(), Error> {
+        let Op::Set(value) = op;
+
+        if self.dot == Some(ctx.dot) && self.value.as_ref() != Some(value) {
+            return Err(Error::ConflictingDot(ctx.dot));
+        }
+        Ok(())
+    }
+}
+
+impl<T: Clone + Debug + PartialEq> InfallibleLocal for LWWRegister<T> {}
+
+impl<T: Clone + Debug + PartialEq> DeltaCmRDT for LWWRegister<T> {
+    /// Returns the register if the peer has not applied the write of its
+    /// current value, or an empty register otherwise.
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        match self.dot {
+            Some(dot) if !applied.contains(&dot) => self.clone(),
+            _ => Self {
+                value: None,
+                dot: None,
+            },
+        }
+    }
 }
 
 #[cfg(test)]
@@ -111,6 +137,7 @@ mod tests {
                 actor: ActorId(1),
                 counter: 100,
             },
+            prev: 0,
             clock: remote_clock,
         };
         replica.apply_remote(Op::Set("future".to_string()), remote_ctx);
@@ -125,6 +152,7 @@ mod tests {
                 actor: ActorId(3),
                 counter: 99,
             },
+            prev: 0,
             clock: Default::default(), // This clock doesn't matter for this part of the test
         };
 
@@ -145,6 +173,7 @@ mod tests {
                 actor: ActorId(1),
                 counter: 100,
             },
+            prev: 0,
             clock: Default::default(),
         };
         replica.apply_remote(Op::Set("from_actor_1".to_string()), remote_ctx);
@@ -155,6 +184,7 @@ mod tests {
                 actor: ActorId(3),
                 counter: 100,
             },
+            prev: 0,
             clock: Default::default(),
         };
         replica.apply_remote(Op::Set("from_actor_3".to_string()), remote_ctx_2);
@@ -235,4 +265,19 @@ mod tests {
         assert_eq!(final_dot.actor, ActorId(2));
         assert_eq!(final_dot.counter, 11);
     }
+
+    #[test]
+    fn test_try_apply_remote_rejects_conflicting_dot() {
+        let mut replica_a = Replica::new(ActorId(1), LWWRegister::default());
+        let (_, ctx) = replica_a.apply(Op::Set("A".to_string()));
+
+        let mut replica_b = Replica::new(ActorId(2), LWWRegister::default());
+        replica_b
+            .try_apply_remote(Op::Set("A".to_string()), ctx.clone())
+            .unwrap();
+
+        let result = replica_b.try_apply_remote(Op::Set("B".to_string()), ctx.clone());
+        assert_eq!(result, Err(Error::ConflictingDot(ctx.dot)));
+        assert_eq!(replica_b.read(), Some("A".to_string()));
+    }
 }
@@ -0,0 +1,117 @@
+use crate::core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal, Lattice};
+use serde::{Deserialize, Serialize};
+
+/// An operation-based register that keeps the greatest value ever written (CmRDT).
+///
+/// Unlike an `LWWRegister`, the winner does not depend on the `Dot` of a write:
+/// `apply` and `merge` both keep the maximum, so the result is the same in any
+/// delivery order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MaxRegister<T: Clone + Ord> {
+    /// The greatest value written so far, if any.
+    pub value: Option<T>,
+}
+
+/// The only operation for a MaxRegister is to write a value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<T> {
+    Set(T),
+}
+
+impl<T: Clone + Ord> Default for MaxRegister<T> {
+    fn default() -> Self {
+        Self { value: None }
+    }
+}
+
+impl<T: Clone + Ord> Lattice for MaxRegister<T> {
+    fn join(&mut self, other: Self) {
+        // `None` is less than any `Some`, so it is the bottom of the lattice.
+        if other.value > self.value {
+            self.value = other.value;
+        }
+    }
+}
+
+impl<T: Clone + Ord> CmRDT for MaxRegister<T> {
+    type Op = Op<T>;
+    type Value = Option<T>;
+
+    fn apply(&mut self, op: Self::Op, _ctx: AddCtx) {
+        let Op::Set(value) = op;
+        self.join(Self { value: Some(value) });
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.join(other);
+    }
+
+    fn read(&self) -> Self::Value {
+        self.value.clone()
+    }
+}
+
+impl<T: Clone + Ord> InfallibleLocal for MaxRegister<T> {}
+
+impl<T: Clone + Ord> DeltaCmRDT for MaxRegister<T> {
+    /// A MaxRegister does not record which op wrote its value, so the delta is
+    /// always the full register.
+    fn delta_since(&self, _applied: &AppliedDots) -> Self {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+
+    #[test]
+    fn test_initial_value_is_none() {
+        let replica = Replica::new(ActorId(1), MaxRegister::<u64>::default());
+        assert_eq!(replica.read(), None);
+    }
+
+    #[test]
+    fn test_keeps_maximum_regardless_of_order() {
+        let mut replica = Replica::new(ActorId(1), MaxRegister::default());
+        replica.apply(Op::Set(5));
+        replica.apply(Op::Set(9));
+        replica.apply(Op::Set(7));
+
+        assert_eq!(replica.read(), Some(9));
+    }
+
+    #[test]
+    fn test_later_dot_does_not_win() {
+        // Arrange: B writes a smaller value after observing A's write.
+        let mut replica_a = Replica::new(ActorId(1), MaxRegister::default());
+        let mut replica_b = Replica::new(ActorId(2), MaxRegister::default());
+        let (op, ctx) = replica_a.apply(Op::Set(100));
+        replica_b.apply_remote(op, ctx);
+        let (op, ctx) = replica_b.apply(Op::Set(1));
+
+        // Act
+        replica_a.apply_remote(op, ctx);
+
+        // Assert
+        assert_eq!(replica_a.read(), Some(100));
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+
+    #[test]
+    fn test_merge_is_commutative() {
+        let mut replica_a = Replica::new(ActorId(1), MaxRegister::default());
+        replica_a.apply(Op::Set("b"));
+        let mut replica_b = Replica::new(ActorId(2), MaxRegister::default());
+        replica_b.apply(Op::Set("a"));
+
+        let mut merged_ab = replica_a.clone();
+        merged_ab.merge(replica_b.state().clone(), replica_b.clock().clone());
+        let mut merged_ba = replica_b.clone();
+        merged_ba.merge(replica_a.state().clone(), replica_a.clock().clone());
+
+        assert_eq!(merged_ab.state(), merged_ba.state());
+        assert_eq!(merged_ab.read(), Some("b"));
+    }
+}
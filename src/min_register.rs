@@ -0,0 +1,103 @@
+use crate::core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal, Lattice};
+use serde::{Deserialize, Serialize};
+
+/// An operation-based register that keeps the smallest value ever written (CmRDT).
+///
+/// The mirror image of a `MaxRegister`: `apply` and `merge` both keep the
+/// minimum, regardless of the `Dot` of each write.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MinRegister<T: Clone + Ord> {
+    /// The smallest value written so far, if any.
+    pub value: Option<T>,
+}
+
+/// The only operation for a MinRegister is to write a value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<T> {
+    Set(T),
+}
+
+impl<T: Clone + Ord> Default for MinRegister<T> {
+    fn default() -> Self {
+        Self { value: None }
+    }
+}
+
+impl<T: Clone + Ord> Lattice for MinRegister<T> {
+    fn join(&mut self, other: Self) {
+        // `None` means nothing was written yet, so it is the bottom of the lattice.
+        match (&self.value, other.value) {
+            (_, None) => {}
+            (Some(current), Some(value)) if *current <= value => {}
+            (_, value) => self.value = value,
+        }
+    }
+}
+
+impl<T: Clone + Ord> CmRDT for MinRegister<T> {
+    type Op = Op<T>;
+    type Value = Option<T>;
+
+    fn apply(&mut self, op: Self::Op, _ctx: AddCtx) {
+        let Op::Set(value) = op;
+        self.join(Self { value: Some(value) });
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.join(other);
+    }
+
+    fn read(&self) -> Self::Value {
+        self.value.clone()
+    }
+}
+
+impl<T: Clone + Ord> InfallibleLocal for MinRegister<T> {}
+
+impl<T: Clone + Ord> DeltaCmRDT for MinRegister<T> {
+    /// A MinRegister does not record which op wrote its value, so the delta is
+    /// always the full register.
+    fn delta_since(&self, _applied: &AppliedDots) -> Self {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+
+    #[test]
+    fn test_initial_value_is_none() {
+        let replica = Replica::new(ActorId(1), MinRegister::<u64>::default());
+        assert_eq!(replica.read(), None);
+    }
+
+    #[test]
+    fn test_keeps_minimum_regardless_of_order() {
+        let mut replica = Replica::new(ActorId(1), MinRegister::default());
+        replica.apply(Op::Set(5));
+        replica.apply(Op::Set(2));
+        replica.apply(Op::Set(7));
+
+        assert_eq!(replica.read(), Some(2));
+    }
+
+    #[test]
+    fn test_merge_is_commutative() {
+        let mut replica_a = Replica::new(ActorId(1), MinRegister::default());
+        replica_a.apply(Op::Set(3));
+        let mut replica_b = Replica::new(ActorId(2), MinRegister::default());
+        replica_b.apply(Op::Set(8));
+        let empty = Replica::new(ActorId(3), MinRegister::default());
+
+        let mut merged_ab = replica_a.clone();
+        merged_ab.merge(replica_b.state().clone(), replica_b.clock().clone());
+        merged_ab.merge(empty.state().clone(), empty.clock().clone());
+        let mut merged_ba = replica_b.clone();
+        merged_ba.merge(replica_a.state().clone(), replica_a.clock().clone());
+
+        assert_eq!(merged_ab.state(), merged_ba.state());
+        assert_eq!(merged_ab.read(), Some(3));
+    }
+}
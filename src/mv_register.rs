@@ -0,0 +1,191 @@
+use crate::{
+    Dot, VClock,
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// An operation-based, Multi-Value Register (CmRDT).
+///
+/// Unlike `LWWRegister`, which uses the `Dot` to pick a single winner, an
+/// MVRegister uses the `VClock` of each write to decide causality. A write
+/// replaces every value its replica had observed, while writes that are
+/// concurrent with each other are all kept as siblings. Reading the register
+/// returns every sibling so the application can resolve the conflict, typically
+/// by writing the resolved value back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+pub struct MVRegister<T: Clone> {
+    /// The concurrently written values, keyed by the dot of the write.
+    #[serde(with = "crate::core::serde_pairs")]
+    pub siblings: BTreeMap<Dot, Sibling<T>>,
+}
+
+/// A single concurrently written value of an `MVRegister`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sibling<T> {
+    /// The causal context of the write that produced this value.
+    pub clock: VClock,
+    pub value: T,
+}
+
+/// The only operation for a MVRegister is to set its value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<T> {
+    Set(T),
+}
+
+impl<T: Clone> Default for MVRegister<T> {
+    fn default() -> Self {
+        Self {
+            siblings: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Clone> MVRegister<T> {
+    /// Adds a sibling unless it has already been superseded, dropping every
+    /// existing sibling that it supersedes.
+    fn insert(&mut self, dot: Dot, sibling: Sibling<T>) {
+        let is_obsolete = self.siblings.contains_key(&dot)
+            || self.siblings.values().any(|s| s.clock.contains(&dot));
+        if is_obsolete {
+            return;
+        }
+
+        self.siblings.retain(|d, _| !sibling.clock.contains(d));
+        self.siblings.insert(dot, sibling);
+    }
+}
+
+impl<T: Clone> CmRDT for MVRegister<T> {
+    type Op = Op<T>;
+    type Value = Vec<T>;
+
+    /// Applies a `Set`, replacing every sibling covered by the op's clock.
+    fn apply(&mut self, op: Self::Op, ctx: AddCtx) {
+        let Op::Set(value) = op;
+
+        self.insert(
+            ctx.dot,
+            Sibling {
+                clock: ctx.clock,
+                value,
+            },
+        );
+    }
+
+    fn merge(&mut self, other: Self) {
+        for (dot, sibling) in other.siblings {
+            self.insert(dot, sibling);
+        }
+    }
+
+    /// Reads every concurrently written value, ordered by the dot of its write.
+    fn read(&self) -> Self::Value {
+        self.siblings.values().map(|s| s.value.clone()).collect()
+    }
+}
+
+impl<T: Clone> InfallibleLocal for MVRegister<T> {}
+
+impl<T: Clone> DeltaCmRDT for MVRegister<T> {
+    /// Returns the siblings whose writes the peer has not applied.
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        Self {
+            siblings: self
+                .siblings
+                .iter()
+                .filter(|(dot, _)| !applied.contains(dot))
+                .map(|(dot, sibling)| (*dot, sibling.clone()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+
+    #[test]
+    fn test_initial_value_is_empty() {
+        let replica = Replica::new(ActorId(1), MVRegister::<String>::default());
+        assert!(replica.read().is_empty());
+    }
+
+    #[test]
+    fn test_sequential_writes_overwrite() {
+        let mut replica = Replica::new(ActorId(1), MVRegister::default());
+        replica.apply(Op::Set("first".to_string()));
+        replica.apply(Op::Set("second".to_string()));
+        assert_eq!(replica.read(), vec!["second".to_string()]);
+    }
+
+    #[test]
+    fn test_concurrent_writes_are_kept_as_siblings() {
+        // Arrange
+        let mut replica_a = Replica::new(ActorId(1), MVRegister::default());
+        let mut replica_b = Replica::new(ActorId(2), MVRegister::default());
+
+        // Act: both replicas write without having seen each other.
+        let (op_a, ctx_a) = replica_a.apply(Op::Set("A"));
+        let (op_b, ctx_b) = replica_b.apply(Op::Set("B"));
+        replica_a.apply_remote(op_b, ctx_b);
+        replica_b.apply_remote(op_a, ctx_a);
+
+        // Assert: neither value is lost.
+        assert_eq!(replica_a.read(), vec!["A", "B"]);
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+
+    #[test]
+    fn test_dominating_write_collapses_siblings() {
+        let mut replica_a = Replica::new(ActorId(1), MVRegister::default());
+        let mut replica_b = Replica::new(ActorId(2), MVRegister::default());
+
+        let (op_a, ctx_a) = replica_a.apply(Op::Set("A"));
+        let (op_b, ctx_b) = replica_b.apply(Op::Set("B"));
+        replica_a.apply_remote(op_b, ctx_b);
+        replica_b.apply_remote(op_a, ctx_a);
+
+        // A resolves the conflict after observing both siblings.
+        let (op, ctx) = replica_a.apply(Op::Set("resolved"));
+        replica_b.apply_remote(op, ctx);
+
+        assert_eq!(replica_a.read(), vec!["resolved"]);
+        assert_eq!(replica_b.read(), vec!["resolved"]);
+    }
+
+    #[test]
+    fn test_stale_write_delivered_late_is_ignored() {
+        let mut replica_a = Replica::new(ActorId(1), MVRegister::default());
+        let old = replica_a.apply(Op::Set(1));
+        let new = replica_a.apply(Op::Set(2));
+
+        let mut replica_b = Replica::new(ActorId(2), MVRegister::default());
+        replica_b.apply_remote(new.0, new.1);
+        replica_b.apply_remote(old.0, old.1);
+
+        assert_eq!(replica_b.read(), vec![2]);
+    }
+
+    #[test]
+    fn test_merge_is_commutative() {
+        let mut replica_a = Replica::new(ActorId(1), MVRegister::default());
+        replica_a.apply(Op::Set(1));
+
+        let mut replica_b = Replica::new(ActorId(2), MVRegister::default());
+        replica_b.apply(Op::Set(2));
+        replica_b.apply(Op::Set(3));
+
+        let mut merged_ab = replica_a.clone();
+        merged_ab.merge(replica_b.state().clone(), replica_b.clock().clone());
+
+        let mut merged_ba = replica_b.clone();
+        merged_ba.merge(replica_a.state().clone(), replica_a.clock().clone());
+
+        assert_eq!(merged_ab.state(), merged_ba.state());
+        assert_eq!(merged_ab.read(), vec![1, 3]);
+    }
+}
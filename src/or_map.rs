@@ -0,0 +1,465 @@
+use crate::{
+    Dot, Error, VClock,
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, btree_map};
+use std::fmt::{self, Debug};
+
+/// An operation-based, Observed-Remove Map (CmRDT) of nested CmRDT values.
+///
+/// An `Update` applies a nested operation to the value of a key, creating it from
+/// `V::default()` if needed, and forwards its own `AddCtx` to the nested `apply`.
+/// Like an `ORSet`, a `Rm` only removes the updates covered by its causal context
+/// and leaves a tombstone for each key, so an update that is concurrent with the
+/// remove survives and keeps the key in the map.
+///
+/// The surviving value of a key must only reflect the surviving updates, which a
+/// nested value cannot tell apart on its own. Every entry therefore keeps the
+/// updates it was built from, and a remove that discards some of them rebuilds
+/// the value from the rest, in dot order.
+///
+/// Once an update is causally stable, [`CmRDT::purge`] folds it into the base
+/// value of its entry, which the value is rebuilt from. Any remove that can
+/// still arrive has observed every stable update, so it discards the whole
+/// base along with the updates it covers.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "K: Serialize, V: Serialize, V::Op: Serialize",
+    deserialize = "K: Deserialize<'de>, V: Deserialize<'de>, V::Op: Deserialize<'de>"
+))]
+pub struct ORMap<K: Clone + Ord, V: CmRDT> {
+    /// The live entries of the map.
+    pub entries: BTreeMap<K, Entry<V>>,
+    /// The merged causal context of every remove seen for each key.
+    pub tombstones: BTreeMap<K, VClock>,
+}
+
+/// The value of a key in an `ORMap`, along with the updates it was built from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "V: Serialize, V::Op: Serialize",
+    deserialize = "V: Deserialize<'de>, V::Op: Deserialize<'de>"
+))]
+pub struct Entry<V: CmRDT> {
+    /// Every surviving update of the key that is not folded into `base`, keyed
+    /// by its dot.
+    #[serde(with = "crate::core::serde_pairs")]
+    pub updates: BTreeMap<Dot, (V::Op, AddCtx)>,
+    /// The nested value with every folded update applied.
+    pub base: V,
+    /// The dots that have been folded into `base`, per actor.
+    pub compacted: VClock,
+    /// The nested value, with every surviving update applied.
+    pub value: V,
+}
+
+/// Operations for an ORMap can update the value of a key, or remove the key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<K, O> {
+    /// Applies a nested operation to the value of the key.
+    Update(K, O),
+    /// Removes the key, along with every update observed by this op.
+    Rm(K),
+}
+
+impl<K: Clone + Ord, V: CmRDT> Default for ORMap<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            tombstones: BTreeMap::new(),
+        }
+    }
+}
+
+// Derived impls would not require the nested ops to implement these traits.
+impl<K: Clone + Ord + Debug, V: CmRDT + Debug> Debug for ORMap<K, V>
+where
+    V::Op: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ORMap")
+            .field("entries", &self.entries)
+            .field("tombstones", &self.tombstones)
+            .finish()
+    }
+}
+
+impl<K: Clone + Ord, V: CmRDT + PartialEq> PartialEq for ORMap<K, V>
+where
+    V::Op: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries && self.tombstones == other.tombstones
+    }
+}
+
+impl<K: Clone + Ord, V: CmRDT + Eq> Eq for ORMap<K, V> where V::Op: Eq {}
+
+impl<V: CmRDT + Default + Clone> Entry<V> {
+    /// Builds a value from its base and updates, applied in dot order.
+    fn rebuild(base: V, compacted: VClock, updates: BTreeMap<Dot, (V::Op, AddCtx)>) -> Self {
+        let mut value = base.clone();
+        for (op, ctx) in updates.values() {
+            value.apply(op.clone(), ctx.clone());
+        }
+        Self {
+            updates,
+            base,
+            compacted,
+            value,
+        }
+    }
+
+    /// Folds the updates covered by the stable clock into the base, and lets the
+    /// nested values discard their own stable metadata.
+    fn purge(&mut self, stable: &VClock) {
+        let (folded, kept) = std::mem::take(&mut self.updates)
+            .into_iter()
+            .partition(|(dot, _)| stable.contains(dot));
+        self.updates = kept;
+
+        let folded: BTreeMap<Dot, (V::Op, AddCtx)> = folded;
+        for (dot, (op, ctx)) in folded {
+            self.base.apply(op, ctx);
+            if dot.counter > self.compacted.get(&dot.actor) {
+                self.compacted.0.insert(dot.actor, dot.counter);
+            }
+        }
+        self.base.purge(stable);
+        self.value.purge(stable);
+    }
+}
+
+impl<K: Clone + Ord, V: CmRDT + Default + Clone> ORMap<K, V> {
+    /// Returns the nested value of the key, if it is in the map.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).map(|entry| &entry.value)
+    }
+
+    /// Returns `true` if the key is in the map.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Iterates over the live keys and their nested values, in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, entry)| (key, &entry.value))
+    }
+
+    /// Returns `true` if the given update has been observed by a remove of `key`.
+    fn is_removed(&self, key: &K, dot: &Dot) -> bool {
+        self.tombstones
+            .get(key)
+            .is_some_and(|clock| clock.contains(dot))
+    }
+
+    /// Drops every update of `key` covered by its tombstone, rebuilding the value
+    /// from the rest, or removing the key if none are left.
+    ///
+    /// A tombstone either covers the whole base or none of it: a remove that had
+    /// not observed a folded update was applied before that update was stable.
+    fn prune(&mut self, key: &K) {
+        let Some(clock) = self.tombstones.get(key) else {
+            return;
+        };
+        let Some(entry) = self.entries.get(key) else {
+            return;
+        };
+        let base_removed = !entry.compacted.0.is_empty() && clock.descends(&entry.compacted);
+        if !base_removed && !entry.updates.keys().any(|dot| clock.contains(dot)) {
+            return;
+        }
+
+        let entry = self.entries.remove(key).expect("entry was just found");
+        let updates: BTreeMap<_, _> = entry
+            .updates
+            .into_iter()
+            .filter(|(dot, _)| !clock.contains(dot))
+            .collect();
+        let (base, compacted) = if base_removed {
+            (V::default(), VClock::default())
+        } else {
+            (entry.base, entry.compacted)
+        };
+        if !updates.is_empty() || !compacted.0.is_empty() {
+            self.entries
+                .insert(key.clone(), Entry::rebuild(base, compacted, updates));
+        }
+    }
+}
+
+impl<K: Clone + Ord, V: CmRDT + Default + Clone> CmRDT for ORMap<K, V> {
+    type Op = Op<K, V::Op>;
+    type Value = BTreeMap<K, V::Value>;
+
+    /// Applies an `Update` to the nested value, or removes every update observed
+    /// by a `Rm`.
+    fn apply(&mut self, op: Self::Op, ctx: AddCtx) {
+        match op {
+            Op::Update(key, op) => {
+                if self.is_removed(&key, &ctx.dot) {
+                    return;
+                }
+                let entry = self.entries.entry(key).or_insert_with(|| Entry {
+                    updates: BTreeMap::new(),
+                    base: V::default(),
+                    compacted: VClock::default(),
+                    value: V::default(),
+                });
+                if entry.compacted.contains(&ctx.dot) {
+                    return;
+                }
+                if let btree_map::Entry::Vacant(slot) = entry.updates.entry(ctx.dot) {
+                    slot.insert((op.clone(), ctx.clone()));
+                    entry.value.apply(op, ctx);
+                }
+            }
+            Op::Rm(key) => {
+                self.tombstones
+                    .entry(key.clone())
+                    .or_default()
+                    .merge(ctx.clock);
+                self.prune(&key);
+            }
+        }
+    }
+
+    /// Merges the nested values and updates key by key, then applies the
+    /// tombstones of both sides.
+    fn merge(&mut self, other: Self) {
+        for (key, other_entry) in other.entries {
+            match self.entries.get_mut(&key) {
+                Some(entry) => {
+                    entry.updates.extend(other_entry.updates);
+                    entry.base.merge(other_entry.base);
+                    entry.compacted.merge(other_entry.compacted);
+                    entry.value.merge(other_entry.value);
+                    let compacted = &entry.compacted;
+                    entry.updates.retain(|dot, _| !compacted.contains(dot));
+                }
+                None => {
+                    self.entries.insert(key, other_entry);
+                }
+            }
+        }
+        for (key, clock) in other.tombstones {
+            self.tombstones.entry(key).or_default().merge(clock);
+        }
+
+        let keys: Vec<K> = self.tombstones.keys().cloned().collect();
+        for key in keys {
+            self.prune(&key);
+        }
+    }
+
+    /// Reads the value of every live key.
+    fn read(&self) -> Self::Value {
+        self.iter()
+            .map(|(key, value)| (key.clone(), value.read()))
+            .collect()
+    }
+
+    /// Folds the stable updates of every entry into its base, forwards the
+    /// purge to the nested values, and drops the tombstones of removes that are
+    /// causally stable, since every update they observed has been delivered
+    /// everywhere.
+    fn purge(&mut self, stable: &VClock) {
+        for entry in self.entries.values_mut() {
+            entry.purge(stable);
+        }
+        self.tombstones
+            .retain(|_, tombstone| !stable.descends(tombstone));
+    }
+
+    /// Forwards an `Update` to the validation of the nested value.
+    fn validate(&self, op: &Self::Op, ctx: &AddCtx) -> Result<(), Error> {
+        let Op::Update(key, op) = op else {
+            return Ok(());
+        };
+
+        match self.get(key) {
+            Some(value) => value.validate(op, ctx),
+            None => V::default().validate(op, ctx),
+        }
+    }
+}
+
+impl<K: Clone + Ord, V: CmRDT + Default + Clone> InfallibleLocal for ORMap<K, V> {}
+
+impl<K: Clone + Ord, V: CmRDT + Default + Clone> DeltaCmRDT for ORMap<K, V> {
+    /// Returns the updates the peer has not applied, with nested values built
+    /// from them alone, and the tombstones of removes it may not have applied.
+    /// Folded updates are stable, so the peer has already applied them.
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        let mut delta = Self::default();
+        for (key, entry) in &self.entries {
+            let unseen: BTreeMap<_, _> = entry
+                .updates
+                .iter()
+                .filter(|(dot, _)| !applied.contains(dot))
+                .map(|(dot, update)| (*dot, update.clone()))
+                .collect();
+            if !unseen.is_empty() {
+                delta.entries.insert(
+                    key.clone(),
+                    Entry::rebuild(V::default(), VClock::default(), unseen),
+                );
+            }
+        }
+        for (key, tombstone) in &self.tombstones {
+            if !applied.covers(tombstone) {
+                delta.tombstones.insert(key.clone(), tombstone.clone());
+            }
+        }
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+    use crate::g_set::{self, GSet};
+    use crate::or_set::{self, ORSet};
+    use crate::pn_counter::{self, PNCounter};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_initial_value_is_empty() {
+        let replica = Replica::new(ActorId(1), ORMap::<u8, PNCounter>::default());
+        assert!(replica.read().is_empty());
+    }
+
+    #[test]
+    fn test_update_and_remove() {
+        // Arrange
+        let mut replica = Replica::new(ActorId(1), ORMap::<_, PNCounter>::default());
+
+        // Act
+        replica.apply(Op::Update("alice", pn_counter::Op::Inc(5)));
+        replica.apply(Op::Update("bob", pn_counter::Op::Inc(2)));
+        replica.apply(Op::Update("alice", pn_counter::Op::Dec(1)));
+        replica.apply(Op::Rm("bob"));
+
+        // Assert
+        assert_eq!(replica.read(), BTreeMap::from([("alice", 4)]));
+        assert!(!replica.state().contains_key(&"bob"));
+    }
+
+    #[test]
+    fn test_update_after_remove_starts_fresh() {
+        let mut replica = Replica::new(ActorId(1), ORMap::<_, GSet<_>>::default());
+        replica.apply(Op::Update(1, g_set::Op::Add("x")));
+        replica.apply(Op::Rm(1));
+        replica.apply(Op::Update(1, g_set::Op::Add("y")));
+
+        assert_eq!(replica.read(), BTreeMap::from([(1, BTreeSet::from(["y"]))]));
+    }
+
+    #[test]
+    fn test_concurrent_update_resurrects_removed_key() {
+        // Arrange: both replicas observe the same initial update.
+        let mut replica_a = Replica::new(ActorId(1), ORMap::<_, GSet<_>>::default());
+        let mut replica_b = Replica::new(ActorId(2), ORMap::<_, GSet<_>>::default());
+
+        let (op, ctx) = replica_a.apply(Op::Update("k", g_set::Op::Add("x")));
+        replica_b.apply_remote(op, ctx);
+
+        // Act: A removes the key while B concurrently updates it.
+        let (rm_op, rm_ctx) = replica_a.apply(Op::Rm("k"));
+        let (update_op, update_ctx) = replica_b.apply(Op::Update("k", g_set::Op::Add("y")));
+
+        replica_a.apply_remote(update_op, update_ctx);
+        replica_b.apply_remote(rm_op, rm_ctx);
+
+        // Assert: only the concurrent update survives, on both sides.
+        assert_eq!(
+            replica_a.read(),
+            BTreeMap::from([("k", BTreeSet::from(["y"]))])
+        );
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+
+    #[test]
+    fn test_merge_is_commutative() {
+        let mut replica_a = Replica::new(ActorId(1), ORMap::<_, PNCounter>::default());
+        replica_a.apply(Op::Update(1, pn_counter::Op::Inc(1)));
+        replica_a.apply(Op::Update(2, pn_counter::Op::Inc(2)));
+
+        let mut replica_b = Replica::new(ActorId(2), ORMap::default());
+        replica_b.merge(replica_a.state().clone(), replica_a.clock().clone());
+        replica_b.apply(Op::Rm(1));
+        replica_b.apply(Op::Update(2, pn_counter::Op::Dec(5)));
+        replica_a.apply(Op::Update(1, pn_counter::Op::Inc(10)));
+
+        let mut merged_ab = replica_a.clone();
+        merged_ab.merge(replica_b.state().clone(), replica_b.clock().clone());
+
+        let mut merged_ba = replica_b.clone();
+        merged_ba.merge(replica_a.state().clone(), replica_a.clock().clone());
+
+        assert_eq!(merged_ab.state(), merged_ba.state());
+        assert_eq!(merged_ab.read(), BTreeMap::from([(1, 10), (2, -3)]));
+    }
+
+    #[test]
+    fn test_try_apply_validates_nested_op() {
+        let mut replica = Replica::new(ActorId(1), ORMap::<_, PNCounter>::default());
+        let (_, ctx) = replica.apply(Op::Update("k", pn_counter::Op::Inc(1)));
+
+        let mut map = replica.state().clone();
+        assert_eq!(
+            map.try_apply(Op::Update("k", pn_counter::Op::Dec(1)), ctx.clone()),
+            Err(Error::ConflictingDot(ctx.dot))
+        );
+        assert_eq!(map.read(), BTreeMap::from([("k", 1)]));
+    }
+
+    #[test]
+    fn test_purge_folds_stable_updates_into_base() {
+        // Arrange
+        let mut replica = Replica::new(ActorId(1), ORMap::<_, PNCounter>::default());
+        for amount in 1..=3 {
+            replica.apply(Op::Update("k", pn_counter::Op::Inc(amount)));
+        }
+        replica.observe_peer(ActorId(2), replica.clock().clone());
+
+        // Act
+        replica.purge();
+        replica.apply(Op::Update("k", pn_counter::Op::Inc(4)));
+
+        // Assert: only the update made after the purge is kept on its own.
+        let entry = &replica.state().entries["k"];
+        assert_eq!(entry.updates.len(), 1);
+        assert_eq!(entry.base.read(), 6);
+        assert_eq!(replica.read(), BTreeMap::from([("k", 10)]));
+
+        // A later remove observed the folded updates, so it discards them too.
+        replica.apply(Op::Rm("k"));
+        assert!(replica.read().is_empty());
+    }
+
+    #[test]
+    fn test_purge_is_forwarded_to_nested_values() {
+        // Arrange: a nested remove leaves a tombstone in the nested set.
+        let mut replica = Replica::new(ActorId(1), ORMap::<_, ORSet<_>>::default());
+        replica.apply(Op::Update("k", or_set::Op::Add("x")));
+        replica.apply(Op::Update("k", or_set::Op::Add("y")));
+        replica.apply(Op::Update("k", or_set::Op::Rm("x")));
+        replica.observe_peer(ActorId(2), replica.clock().clone());
+
+        // Act
+        replica.purge();
+
+        // Assert
+        let entry = &replica.state().entries["k"];
+        assert!(entry.updates.is_empty());
+        assert!(entry.base.tombstones.is_empty());
+        assert!(entry.value.tombstones.is_empty());
+        assert_eq!(
+            replica.read(),
+            BTreeMap::from([("k", BTreeSet::from(["y"]))])
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    Dot, VClock,
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An operation-based, Observed-Remove Set (CmRDT) with add-wins semantics.
+///
+/// Every `Add` is tagged with the unique `Dot` of its operation. A `Rm` does not
+/// delete the element outright; instead it removes only the add-dots that are
+/// covered by its causal context (the `VClock` of the issuing replica). An `Add`
+/// that is concurrent with the `Rm` carries a dot the remover has never observed,
+/// so it survives and the element stays in the set.
+///
+/// The causal context of each remove is kept per element as a tombstone, so an
+/// `Add` that is delivered after a remove which already observed it is still
+/// discarded. This keeps the set convergent under unordered delivery.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ORSet<T: Clone + Ord> {
+    /// The live add-dots for each element currently in the set.
+    pub entries: BTreeMap<T, BTreeSet<Dot>>,
+    /// The merged causal context of every remove seen for each element.
+    pub tombstones: BTreeMap<T, VClock>,
+}
+
+/// Operations for an ORSet can add or remove a value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<T> {
+    Add(T),
+    Rm(T),
+}
+
+impl<T: Clone + Ord> Default for ORSet<T> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            tombstones: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Clone + Ord> ORSet<T> {
+    /// Returns `true` if the value is currently in the set.
+    pub fn contains(&self, value: &T) -> bool {
+        self.entries.contains_key(value)
+    }
+
+    /// Returns `true` if the given add-dot has been observed by a remove of `value`.
+    fn is_removed(&self, value: &T, dot: &Dot) -> bool {
+        self.tombstones
+            .get(value)
+            .is_some_and(|clock| clock.contains(dot))
+    }
+
+    /// Drops every add-dot of `value` that is covered by its tombstone.
+    fn prune(&mut self, value: &T) {
+        let Some(clock) = self.tombstones.get(value) else {
+            return;
+        };
+
+        if let Some(dots) = self.entries.get_mut(value) {
+            dots.retain(|dot| !clock.contains(dot));
+            if dots.is_empty() {
+                self.entries.remove(value);
+            }
+        }
+    }
+}
+
+impl<T: Clone + Ord> CmRDT for ORSet<T> {
+    type Op = Op<T>;
+    type Value = BTreeSet<T>;
+
+    /// Tags an `Add` with its dot, or removes every add-dot observed by a `Rm`.
+    fn apply(&mut self, op: Self::Op, ctx: AddCtx) {
+        match op {
+            Op::Add(value) => {
+                if !self.is_removed(&value, &ctx.dot) {
+                    self.entries.entry(value).or_default().insert(ctx.dot);
+                }
+            }
+            Op::Rm(value) => {
+                self.tombstones
+                    .entry(value.clone())
+                    .or_default()
+                    .merge(ctx.clock);
+                self.prune(&value);
+            }
+        }
+    }
+
+    /// Takes the union of both add-dots and tombstones, then discards any add-dot
+    /// that a remove on either side has observed.
+    fn merge(&mut self, other: Self) {
+        for (value, dots) in other.entries {
+            self.entries.entry(value).or_default().extend(dots);
+        }
+        for (value, clock) in other.tombstones {
+            self.tombstones.entry(value).or_default().merge(clock);
+        }
+
+        let values: Vec<T> = self.tombstones.keys().cloned().collect();
+        for value in values {
+            self.prune(&value);
+        }
+    }
+
+    fn read(&self) -> Self::Value {
+        self.entries.keys().cloned().collect()
+    }
+
+    /// Drops the tombstones of removes that are causally stable. Every add they
+    /// observed has been delivered everywhere, so none can still arrive.
+    fn purge(&mut self, stable: &VClock) {
+        self.tombstones
+            .retain(|_, tombstone| !stable.descends(tombstone));
+    }
+}
+
+impl<T: Clone + Ord> InfallibleLocal for ORSet<T> {}
+
+impl<T: Clone + Ord> DeltaCmRDT for ORSet<T> {
+    /// Returns the add-dots the peer has not applied, and the tombstones that
+    /// record a remove it may not have applied.
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        let mut delta = Self::default();
+        for (value, dots) in &self.entries {
+            let unseen: BTreeSet<Dot> = dots
+                .iter()
+                .filter(|d| !applied.contains(d))
+                .cloned()
+                .collect();
+            if !unseen.is_empty() {
+                delta.entries.insert(value.clone(), unseen);
+            }
+        }
+        for (value, tombstone) in &self.tombstones {
+            if !applied.covers(tombstone) {
+                delta.tombstones.insert(value.clone(), tombstone.clone());
+            }
+        }
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+
+    #[test]
+    fn test_initial_value_is_empty() {
+        let replica = Replica::new(ActorId(1), ORSet::<i32>::default());
+        assert!(replica.read().is_empty());
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        // Arrange
+        let mut replica = Replica::new(ActorId(1), ORSet::default());
+
+        // Act
+        replica.apply(Op::Add("hello".to_string()));
+        replica.apply(Op::Add("world".to_string()));
+        replica.apply(Op::Rm("hello".to_string()));
+
+        // Assert
+        assert_eq!(replica.read(), BTreeSet::from(["world".to_string()]));
+        assert!(!replica.state().contains(&"hello".to_string()));
+    }
+
+    #[test]
+    fn test_re_add_after_remove() {
+        let mut replica = Replica::new(ActorId(1), ORSet::default());
+        replica.apply(Op::Add(1));
+        replica.apply(Op::Rm(1));
+        replica.apply(Op::Add(1));
+
+        assert_eq!(replica.read(), BTreeSet::from([1]));
+    }
+
+    #[test]
+    fn test_concurrent_add_wins_over_remove() {
+        // Arrange: both replicas observe the same initial add.
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default());
+
+        let (op, ctx) = replica_a.apply(Op::Add(7));
+        replica_b.apply_remote(op, ctx);
+
+        // Act: A removes the element while B concurrently re-adds it.
+        let (rm_op, rm_ctx) = replica_a.apply(Op::Rm(7));
+        let (add_op, add_ctx) = replica_b.apply(Op::Add(7));
+
+        replica_a.apply_remote(add_op, add_ctx);
+        replica_b.apply_remote(rm_op, rm_ctx);
+
+        // Assert: the concurrent add survives on both sides.
+        assert_eq!(replica_a.read(), BTreeSet::from([7]));
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+
+    #[test]
+    fn test_remove_delivered_before_observed_add() {
+        // Arrange
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default());
+
+        let (add_op, add_ctx) = replica_a.apply(Op::Add(3));
+        let (rm_op, rm_ctx) = replica_a.apply(Op::Rm(3));
+
+        // Act: B receives the remove before the add it observed.
+        replica_b.apply_remote(rm_op, rm_ctx);
+        replica_b.apply_remote(add_op, add_ctx);
+
+        // Assert
+        assert!(replica_b.read().is_empty());
+    }
+
+    #[test]
+    fn test_merge_applies_remote_removes() {
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        replica_a.apply(Op::Add(1));
+        replica_a.apply(Op::Add(2));
+
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default());
+        replica_b.merge(replica_a.state().clone(), replica_a.clock().clone());
+        replica_b.apply(Op::Rm(1));
+
+        replica_a.merge(replica_b.state().clone(), replica_b.clock().clone());
+
+        assert_eq!(replica_a.read(), BTreeSet::from([2]));
+    }
+}
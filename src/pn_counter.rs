@@ -1,4 +1,5 @@
-use crate::core::{AddCtx, CmRDT};
+use crate::Error;
+use crate::core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal, VClock};
 use crate::g_counter::{self, GCounter};
 use serde::{Deserialize, Serialize};
 
@@ -10,12 +11,21 @@ pub struct PNCounter {
 }
 
 /// Operations for a PNCounter can be increments or decrements.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Op {
     Inc(u64),
     Dec(u64),
 }
 
+impl PNCounter {
+    /// Folds every increment and decrement covered by the given causally stable
+    /// clock into per-actor totals. See [`GCounter::compact`].
+    pub fn compact(&mut self, stable: &VClock) {
+        self.increments.compact(stable);
+        self.decrements.compact(stable);
+    }
+}
+
 impl CmRDT for PNCounter {
     type Op = Op;
     type Value = i64;
@@ -35,12 +45,41 @@ impl CmRDT for PNCounter {
     fn read(&self) -> Self::Value {
         self.increments.read() as i64 - self.decrements.read() as i64
     }
+
+    fn purge(&mut self, stable: &VClock) {
+        self.compact(stable);
+    }
+
+    /// Rejects an op whose dot was already recorded, either with a different
+    /// amount or on the opposite side of the counter.
+    fn validate(&self, op: &Self::Op, ctx: &AddCtx) -> Result<(), Error> {
+        let (same, opposite, amount) = match op {
+            Op::Inc(amount) => (&self.increments, &self.decrements, amount),
+            Op::Dec(amount) => (&self.decrements, &self.increments, amount),
+        };
+
+        if opposite.ops.contains_key(&ctx.dot) {
+            return Err(Error::ConflictingDot(ctx.dot));
+        }
+        same.validate(&g_counter::Op::Inc(*amount), ctx)
+    }
+}
+
+impl InfallibleLocal for PNCounter {}
+
+impl DeltaCmRDT for PNCounter {
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        Self {
+            increments: self.increments.delta_since(applied),
+            decrements: self.decrements.delta_since(applied),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{ActorId, Replica};
+    use crate::core::{ActorId, Dot, Replica};
 
     #[test]
     fn test_apply_inc_and_dec() {
@@ -108,4 +147,38 @@ mod tests {
         assert_eq!(merged_ba.read(), 5);
         assert_eq!(merged_ab.state(), merged_ba.state());
     }
+
+    #[test]
+    fn test_try_apply_rejects_zero_counter() {
+        // Arrange
+        let mut counter = PNCounter::default();
+        let dot = Dot {
+            actor: ActorId(1),
+            counter: 0,
+        };
+        let ctx = AddCtx {
+            dot,
+            prev: 0,
+            clock: VClock::default(),
+        };
+
+        // Act
+        let result = counter.try_apply(Op::Inc(5), ctx);
+
+        // Assert
+        assert_eq!(result, Err(Error::ZeroCounter(dot)));
+        assert_eq!(counter.read(), 0);
+    }
+
+    #[test]
+    fn test_try_apply_rejects_dot_reused_on_opposite_side() {
+        let mut replica = Replica::new(ActorId(1), PNCounter::default());
+        let (_, ctx) = replica.apply(Op::Inc(5));
+
+        let mut counter = replica.state().clone();
+        let result = counter.try_apply(Op::Dec(5), ctx.clone());
+
+        assert_eq!(result, Err(Error::ConflictingDot(ctx.dot)));
+        assert_eq!(counter.read(), 5);
+    }
 }
@@ -0,0 +1,195 @@
+use crate::{
+    Dot, Error, VClock,
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// An operation-based counter that can be reset to zero (CmRDT).
+///
+/// Like a `PNCounter`, it keeps every increment and decrement keyed by its `Dot`.
+/// A `Reset` discards exactly the operations covered by the causal context of the
+/// replica that issued it, so operations concurrent with the reset survive it.
+/// The contexts of every reset are merged into `reset`, so an operation that is
+/// delivered after a reset which already observed it is still discarded.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ResetCounter {
+    #[serde(with = "crate::core::serde_pairs")]
+    pub increments: BTreeMap<Dot, u64>,
+    #[serde(with = "crate::core::serde_pairs")]
+    pub decrements: BTreeMap<Dot, u64>,
+    /// The merged causal context of every reset.
+    pub reset: VClock,
+}
+
+/// Operations for a ResetCounter can increment, decrement, or reset it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op {
+    Inc(u64),
+    Dec(u64),
+    /// Discards every increment and decrement observed by the issuing replica.
+    Reset,
+}
+
+impl ResetCounter {
+    /// Drops every operation covered by the reset clock.
+    fn prune(&mut self) {
+        let reset = &self.reset;
+        self.increments.retain(|dot, _| !reset.contains(dot));
+        self.decrements.retain(|dot, _| !reset.contains(dot));
+    }
+}
+
+impl CmRDT for ResetCounter {
+    type Op = Op;
+    type Value = i64;
+
+    fn apply(&mut self, op: Self::Op, ctx: AddCtx) {
+        match op {
+            Op::Inc(amount) if !self.reset.contains(&ctx.dot) => {
+                self.increments.insert(ctx.dot, amount);
+            }
+            Op::Dec(amount) if !self.reset.contains(&ctx.dot) => {
+                self.decrements.insert(ctx.dot, amount);
+            }
+            Op::Inc(_) | Op::Dec(_) => {
+                // A reset has already observed this operation.
+            }
+            Op::Reset => {
+                self.reset.merge(ctx.clock);
+                self.prune();
+            }
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.increments.extend(other.increments);
+        self.decrements.extend(other.decrements);
+        self.reset.merge(other.reset);
+        self.prune();
+    }
+
+    fn read(&self) -> Self::Value {
+        self.increments.values().sum::<u64>() as i64 - self.decrements.values().sum::<u64>() as i64
+    }
+
+    /// Forgets the reset clock once every reset is causally stable, since every
+    /// operation it covers has already been delivered and discarded.
+    fn purge(&mut self, stable: &VClock) {
+        if stable.descends(&self.reset) {
+            self.reset = VClock::default();
+        }
+    }
+
+    /// Rejects an op whose dot was already recorded with a different operation.
+    fn validate(&self, op: &Self::Op, ctx: &AddCtx) -> Result<(), Error> {
+        let recorded = match (self.increments.get(&ctx.dot), self.decrements.get(&ctx.dot)) {
+            (Some(amount), _) => Some(Op::Inc(*amount)),
+            (_, Some(amount)) => Some(Op::Dec(*amount)),
+            _ => None,
+        };
+
+        match recorded {
+            Some(recorded) if recorded != *op => Err(Error::ConflictingDot(ctx.dot)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl InfallibleLocal for ResetCounter {}
+
+impl DeltaCmRDT for ResetCounter {
+    /// Returns the operations the peer has not applied, along with the reset
+    /// clock if it may not have applied every reset.
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        let unseen = |ops: &BTreeMap<Dot, u64>| {
+            ops.iter()
+                .filter(|(dot, _)| !applied.contains(dot))
+                .map(|(dot, amount)| (*dot, *amount))
+                .collect()
+        };
+        Self {
+            increments: unseen(&self.increments),
+            decrements: unseen(&self.decrements),
+            reset: if applied.covers(&self.reset) {
+                VClock::default()
+            } else {
+                self.reset.clone()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+
+    #[test]
+    fn test_initial_value_is_zero() {
+        let replica = Replica::new(ActorId(1), ResetCounter::default());
+        assert_eq!(replica.read(), 0);
+    }
+
+    #[test]
+    fn test_reset_clears_observed_ops() {
+        // Arrange
+        let mut replica = Replica::new(ActorId(1), ResetCounter::default());
+        replica.apply(Op::Inc(10));
+        replica.apply(Op::Dec(3));
+
+        // Act
+        replica.apply(Op::Reset);
+        replica.apply(Op::Inc(2));
+
+        // Assert
+        assert_eq!(replica.read(), 2);
+        assert_eq!(replica.state().increments.len(), 1);
+        assert!(replica.state().decrements.is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_increment_survives_reset() {
+        // Arrange: both replicas observe the same increment.
+        let mut replica_a = Replica::new(ActorId(1), ResetCounter::default());
+        let mut replica_b = Replica::new(ActorId(2), ResetCounter::default());
+        let (op, ctx) = replica_a.apply(Op::Inc(5));
+        replica_b.apply_remote(op, ctx);
+
+        // Act: A resets while B concurrently increments.
+        let (reset, reset_ctx) = replica_a.apply(Op::Reset);
+        let (inc, inc_ctx) = replica_b.apply(Op::Inc(7));
+        replica_a.apply_remote(inc, inc_ctx);
+        replica_b.apply_remote(reset, reset_ctx);
+
+        // Assert
+        assert_eq!(replica_a.read(), 7);
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+
+    #[test]
+    fn test_reset_delivered_before_observed_increment() {
+        let mut replica_a = Replica::new(ActorId(1), ResetCounter::default());
+        let inc = replica_a.apply(Op::Inc(5));
+        let reset = replica_a.apply(Op::Reset);
+
+        let mut replica_b = Replica::new(ActorId(2), ResetCounter::default());
+        replica_b.apply_remote(reset.0, reset.1);
+        replica_b.apply_remote(inc.0, inc.1);
+
+        assert_eq!(replica_b.read(), 0);
+    }
+
+    #[test]
+    fn test_try_apply_rejects_conflicting_dot() {
+        let mut replica = Replica::new(ActorId(1), ResetCounter::default());
+        let (_, ctx) = replica.apply(Op::Inc(5));
+
+        let mut counter = replica.state().clone();
+        assert_eq!(
+            counter.try_apply(Op::Dec(5), ctx.clone()),
+            Err(Error::ConflictingDot(ctx.dot))
+        );
+        assert_eq!(counter.try_apply(Op::Inc(5), ctx), Ok(()));
+    }
+}
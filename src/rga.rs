@@ -0,0 +1,390 @@
+use crate::{
+    Dot, Error, VClock,
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal, Replica},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An operation-based, Replicated Growable Array (CmRDT).
+///
+/// Every element is identified by the `Dot` of the operation that inserted it and
+/// remembers the id of the element it was inserted after (its parent, or `None`
+/// for the head of the sequence). The sequence is read by walking this tree
+/// depth-first, visiting the children of each element in descending `Dot` order.
+/// Because the `Dot` of an insert is always greater than the `Dot` of anything
+/// its replica had observed, a newer insert after the same parent is placed
+/// closer to it, and concurrent inserts are ordered deterministically by the
+/// `Dot` total order (counter, then actor).
+///
+/// Deleted elements are kept as tombstones so that concurrent inserts can still
+/// anchor to them. A tombstone with no children is dropped by `CmRDT::purge`
+/// once its delete is causally stable, since no insert can anchor to it anymore.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+pub struct Rga<T: Clone> {
+    /// Every inserted element, keyed by its id.
+    #[serde(with = "crate::core::serde_pairs")]
+    pub elements: BTreeMap<Dot, Element<T>>,
+    /// The ids of every deleted element, mapped to the dot of the earliest
+    /// delete seen for it.
+    #[serde(with = "crate::core::serde_pairs")]
+    pub deleted: BTreeMap<Dot, Dot>,
+}
+
+/// A single element of an `Rga`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Element<T> {
+    /// The id of the element this one was inserted after.
+    pub parent: Option<Dot>,
+    pub value: T,
+}
+
+/// Operations for an Rga can insert a value after an element, or delete an element.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<T> {
+    /// Inserts a value after the element with the given id, or at the head if `None`.
+    InsertAfter(Option<Dot>, T),
+    /// Deletes the element with the given id.
+    Delete(Dot),
+}
+
+impl<T: Clone> Default for Rga<T> {
+    fn default() -> Self {
+        Self {
+            elements: BTreeMap::new(),
+            deleted: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Clone> Rga<T> {
+    /// Returns the ids of every element in sequence order, including deleted ones.
+    ///
+    /// Elements whose parent has not been delivered yet are not reachable and
+    /// are omitted until it arrives.
+    fn walk(&self) -> Vec<Dot> {
+        let mut children: BTreeMap<Option<Dot>, Vec<Dot>> = BTreeMap::new();
+        for (id, element) in &self.elements {
+            children.entry(element.parent).or_default().push(*id);
+        }
+
+        let mut order = Vec::with_capacity(self.elements.len());
+        // Children are collected in ascending `Dot` order, so pushing them onto
+        // the stack as-is visits the greatest one first.
+        let mut stack = children.get(&None).cloned().unwrap_or_default();
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            if let Some(ids) = children.get(&Some(id)) {
+                stack.extend(ids);
+            }
+        }
+        order
+    }
+
+    /// Records a delete of `id`, keeping the earliest delete dot seen for it.
+    fn delete(&mut self, id: Dot, dot: Dot) {
+        self.deleted
+            .entry(id)
+            .and_modify(|existing| *existing = (*existing).min(dot))
+            .or_insert(dot);
+    }
+
+    /// Returns the ids of the visible elements in sequence order.
+    pub fn ids(&self) -> Vec<Dot> {
+        self.walk()
+            .into_iter()
+            .filter(|id| !self.deleted.contains_key(id))
+            .collect()
+    }
+}
+
+impl<T: Clone> CmRDT for Rga<T> {
+    type Op = Op<T>;
+    type Value = Vec<T>;
+
+    /// Inserts a new element identified by the op's dot, or tombstones an element.
+    fn apply(&mut self, op: Self::Op, ctx: AddCtx) {
+        match op {
+            Op::InsertAfter(parent, value) => {
+                self.elements.insert(ctx.dot, Element { parent, value });
+            }
+            Op::Delete(id) => self.delete(id, ctx.dot),
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        // Elements are immutable once inserted, so a plain union is enough.
+        self.elements.extend(other.elements);
+        for (id, dot) in other.deleted {
+            self.delete(id, dot);
+        }
+    }
+
+    /// Reads the visible elements in sequence order.
+    fn read(&self) -> Self::Value {
+        self.ids()
+            .iter()
+            .map(|id| self.elements[id].value.clone())
+            .collect()
+    }
+
+    /// Drops every deleted element that has no children and whose delete is
+    /// covered by the stable clock, repeating until none are left, as dropping a
+    /// child may leave its parent without children.
+    fn purge(&mut self, stable: &VClock) {
+        loop {
+            let parents: BTreeSet<Dot> = self
+                .elements
+                .values()
+                .filter_map(|element| element.parent)
+                .collect();
+            let purgeable: Vec<Dot> = self
+                .deleted
+                .iter()
+                .filter(|(id, dot)| {
+                    stable.contains(dot) && self.elements.contains_key(id) && !parents.contains(id)
+                })
+                .map(|(id, _)| *id)
+                .collect();
+            if purgeable.is_empty() {
+                return;
+            }
+
+            for id in purgeable {
+                self.elements.remove(&id);
+                self.deleted.remove(&id);
+            }
+        }
+    }
+
+    /// Rejects an op that references an element its replica had not observed,
+    /// including the element the op itself would insert.
+    fn validate(&self, op: &Self::Op, ctx: &AddCtx) -> Result<(), Error> {
+        let referenced = match op {
+            Op::InsertAfter(parent, _) => *parent,
+            Op::Delete(id) => Some(*id),
+        };
+
+        match referenced {
+            Some(id) if id == ctx.dot || !ctx.clock.contains(&id) => Err(Error::UnobservedDot(id)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<T: Clone> InfallibleLocal for Rga<T> {}
+
+impl<T: Clone> DeltaCmRDT for Rga<T> {
+    /// Returns the elements and deletes whose dots the peer has not applied.
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        Self {
+            elements: self
+                .elements
+                .iter()
+                .filter(|(id, _)| !applied.contains(id))
+                .map(|(id, element)| (*id, element.clone()))
+                .collect(),
+            deleted: self
+                .deleted
+                .iter()
+                .filter(|(_, dot)| !applied.contains(dot))
+                .map(|(id, dot)| (*id, *dot))
+                .collect(),
+        }
+    }
+}
+
+impl<T: Clone> Replica<Rga<T>> {
+    /// Inserts a value so that it ends up at `index` of the visible sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of visible elements.
+    pub fn insert(&mut self, index: usize, value: T) -> (Op<T>, AddCtx) {
+        let ids = self.state().ids();
+        assert!(
+            index <= ids.len(),
+            "insertion index (is {index}) should be <= len (is {})",
+            ids.len()
+        );
+
+        let parent = index.checked_sub(1).map(|i| ids[i]);
+        self.apply(Op::InsertAfter(parent, value))
+    }
+
+    /// Deletes the value at `index` of the visible sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn delete(&mut self, index: usize) -> (Op<T>, AddCtx) {
+        let ids = self.state().ids();
+        assert!(
+            index < ids.len(),
+            "removal index (is {index}) should be < len (is {})",
+            ids.len()
+        );
+
+        self.apply(Op::Delete(ids[index]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+
+    #[test]
+    fn test_initial_value_is_empty() {
+        let replica = Replica::new(ActorId(1), Rga::<char>::default());
+        assert!(replica.read().is_empty());
+    }
+
+    #[test]
+    fn test_insert_by_index() {
+        // Arrange
+        let mut replica = Replica::new(ActorId(1), Rga::default());
+
+        // Act
+        replica.insert(0, 'a');
+        replica.insert(1, 'c');
+        replica.insert(1, 'b');
+        replica.insert(0, '_');
+
+        // Assert
+        assert_eq!(replica.read(), vec!['_', 'a', 'b', 'c']);
+    }
+
+    #[test]
+    fn test_delete_by_index() {
+        let mut replica = Replica::new(ActorId(1), Rga::default());
+        for (i, c) in "abc".chars().enumerate() {
+            replica.insert(i, c);
+        }
+
+        replica.delete(1);
+        assert_eq!(replica.read(), vec!['a', 'c']);
+
+        // Inserting after a deleted position still lands where expected.
+        replica.insert(1, 'x');
+        assert_eq!(replica.read(), vec!['a', 'x', 'c']);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_delete_out_of_bounds_panics() {
+        let mut replica = Replica::new(ActorId(1), Rga::<char>::default());
+        replica.delete(0);
+    }
+
+    #[test]
+    fn test_concurrent_inserts_converge() {
+        // Arrange: both replicas start from the same "ac".
+        let mut replica_a = Replica::new(ActorId(1), Rga::default());
+        let mut replica_b = Replica::new(ActorId(2), Rga::default());
+        for (i, c) in "ac".chars().enumerate() {
+            let (op, ctx) = replica_a.insert(i, c);
+            replica_b.apply_remote(op, ctx);
+        }
+
+        // Act: both insert concurrently at the same position.
+        let (op_a, ctx_a) = replica_a.insert(1, 'x');
+        let (op_b, ctx_b) = replica_b.insert(1, 'y');
+        replica_a.apply_remote(op_b, ctx_b);
+        replica_b.apply_remote(op_a, ctx_a);
+
+        // Assert: the dots tie on counter, so the greater actor goes first.
+        assert_eq!(replica_a.read(), vec!['a', 'y', 'x', 'c']);
+        assert_eq!(replica_a.read(), replica_b.read());
+    }
+
+    #[test]
+    fn test_out_of_order_delivery() {
+        let mut replica_a = Replica::new(ActorId(1), Rga::default());
+        let ops: Vec<_> = "abc"
+            .chars()
+            .enumerate()
+            .map(|(i, c)| replica_a.insert(i, c))
+            .collect();
+        let delete = replica_a.delete(0);
+
+        let mut replica_b = Replica::new(ActorId(2), Rga::default());
+        replica_b.apply_remote(delete.0, delete.1);
+        for (op, ctx) in ops.into_iter().rev() {
+            replica_b.apply_remote(op, ctx);
+        }
+
+        assert_eq!(replica_b.read(), vec!['b', 'c']);
+    }
+
+    #[test]
+    fn test_merge_is_commutative() {
+        let mut replica_a = Replica::new(ActorId(1), Rga::default());
+        replica_a.insert(0, 1);
+        replica_a.insert(1, 2);
+
+        let mut replica_b = Replica::new(ActorId(2), Rga::default());
+        replica_b.insert(0, 3);
+        replica_b.delete(0);
+        replica_b.insert(0, 4);
+
+        let mut merged_ab = replica_a.clone();
+        merged_ab.merge(replica_b.state().clone(), replica_b.clock().clone());
+
+        let mut merged_ba = replica_b.clone();
+        merged_ba.merge(replica_a.state().clone(), replica_a.clock().clone());
+
+        assert_eq!(merged_ab.state(), merged_ba.state());
+        assert_eq!(merged_ab.read(), vec![4, 1, 2]);
+    }
+
+    #[test]
+    fn test_try_apply_remote_rejects_unobserved_references() {
+        let mut replica_a = Replica::new(ActorId(1), Rga::default());
+        let (_, ctx) = replica_a.insert(0, 'a');
+
+        // An op generated by a replica that never observed 'a' cannot reference it.
+        let mut replica_b = Replica::new(ActorId(2), Rga::default());
+        let (_, unrelated_ctx) = replica_b.insert(0, 'b');
+        let mut replica_c = Replica::new(ActorId(3), Rga::default());
+
+        let insert =
+            replica_c.try_apply_remote(Op::InsertAfter(Some(ctx.dot), 'x'), unrelated_ctx.clone());
+        let delete = replica_c.try_apply_remote(Op::Delete(ctx.dot), unrelated_ctx.clone());
+        let self_reference =
+            replica_c.try_apply_remote(Op::Delete(unrelated_ctx.dot), unrelated_ctx.clone());
+
+        assert_eq!(insert, Err(Error::UnobservedDot(ctx.dot)));
+        assert_eq!(delete, Err(Error::UnobservedDot(ctx.dot)));
+        assert_eq!(self_reference, Err(Error::UnobservedDot(unrelated_ctx.dot)));
+        assert!(replica_c.read().is_empty());
+    }
+
+    #[test]
+    fn test_purge_drops_stable_leaf_tombstones() {
+        // Arrange: "abc" with 'a' and 'c' deleted; 'b' is anchored to 'a'.
+        let mut replica = Replica::new(ActorId(1), Rga::default());
+        for (i, c) in "abc".chars().enumerate() {
+            replica.insert(i, c);
+        }
+        replica.delete(2);
+        replica.delete(0);
+        let stable = replica.clock().clone();
+        let (_, ctx) = replica.delete(0);
+
+        // Act
+        let mut rga = replica.state().clone();
+        rga.purge(&stable);
+
+        // Assert: only 'c' is dropped. 'a' still has a child, and the delete
+        // of 'b' is not stable yet.
+        assert_eq!(rga.elements.len(), 2);
+        assert_eq!(rga.deleted.len(), 2);
+        assert!(rga.read().is_empty());
+
+        // Once every delete is stable, the whole chain is dropped.
+        rga.purge(&ctx.clock);
+        assert!(rga.elements.is_empty());
+        assert!(rga.deleted.is_empty());
+    }
+}
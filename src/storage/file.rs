@@ -0,0 +1,541 @@
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::core::{AddCtx, CmRDT, Envelope, FORMAT_VERSION, Snapshot};
+use crate::storage::{Recovered, Storage};
+
+const SNAPSHOT_FILE: &str = "snapshot.json";
+const SNAPSHOT_TMP_FILE: &str = "snapshot.json.tmp";
+const LOG_FILE: &str = "log.jsonl";
+
+/// A [`Storage`] that keeps a replica in a directory of JSON files.
+///
+/// The latest snapshot is kept in `snapshot.json`, and the operations applied
+/// since are appended to `log.jsonl` as one [`Envelope`] per line, or one JSON
+/// array of envelopes per batch. Every write is synced to disk before it
+/// returns.
+///
+/// A snapshot is written to a temporary file and renamed over the previous one,
+/// so a crash leaves either the old or the new snapshot in place. A crash in
+/// the middle of an append leaves a partial last line in the log, which is
+/// discarded by [`Storage::load`]; since it was never acknowledged, that
+/// operation was never sent to any peer. A batch is written as a single line,
+/// so it is discarded as a whole.
+///
+/// An append that fails without a crash is cut back off the log, so that the
+/// next one starts on a fresh line and the failed operation is never replayed
+/// under a dot the replica hands out again. If even that fails, the storage
+/// refuses every further append until a snapshot empties the log.
+#[derive(Debug)]
+pub struct FileStorage {
+    dir: PathBuf,
+    log: Box<dyn LogFile>,
+    poisoned: bool,
+}
+
+/// The file the log is appended to.
+trait LogFile: Write + fmt::Debug + Send + Sync {
+    fn len(&self) -> io::Result<u64>;
+    fn set_len(&self, len: u64) -> io::Result<()>;
+    fn sync_data(&self) -> io::Result<()>;
+    fn sync_all(&self) -> io::Result<()>;
+}
+
+impl LogFile for File {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn set_len(&self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        File::sync_data(self)
+    }
+
+    fn sync_all(&self) -> io::Result<()> {
+        File::sync_all(self)
+    }
+}
+
+impl FileStorage {
+    /// Opens the storage in the given directory, creating it if needed.
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE))?;
+        Ok(Self {
+            dir,
+            log: Box::new(log),
+            poisoned: false,
+        })
+    }
+
+    /// Returns the directory the storage is kept in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Appends a value to the log as a single line.
+    ///
+    /// If the write or the sync fails, the log is truncated back to its previous
+    /// length, or marked as poisoned if that fails too.
+    fn append_line(&mut self, value: &impl Serialize) -> io::Result<()> {
+        if self.poisoned {
+            return Err(io::Error::other(
+                "log left inconsistent by a failed append, a snapshot is required",
+            ));
+        }
+
+        let mut line = serde_json::to_vec(value).map_err(invalid_data)?;
+        line.push(b'\n');
+
+        let len = self.log.len()?;
+        let result = self
+            .log
+            .write_all(&line)
+            .and_then(|()| self.log.sync_data());
+        if result.is_err()
+            && self
+                .log
+                .set_len(len)
+                .and_then(|()| self.log.sync_data())
+                .is_err()
+        {
+            self.poisoned = true;
+        }
+        result
+    }
+
+    /// Makes the latest rename in the directory durable.
+    fn sync_dir(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        File::open(&self.dir)?.sync_all()?;
+        Ok(())
+    }
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+impl<T> Storage<T> for FileStorage
+where
+    T: CmRDT + Serialize + DeserializeOwned,
+    T::Op: Serialize + DeserializeOwned,
+{
+    type Error = io::Error;
+
+    fn append(&mut self, op: &T::Op, ctx: &AddCtx) -> io::Result<()> {
+        let envelope = Envelope::<T>::new(op.clone(), ctx.clone());
+        self.append_line(&envelope)
+    }
+
+    fn append_batch(&mut self, ops: &[(&T::Op, &AddCtx)]) -> io::Result<()> {
+        let envelopes: Vec<_> = ops
+            .iter()
+            .map(|&(op, ctx)| Envelope::<T>::new(op.clone(), ctx.clone()))
+            .collect();
+        self.append_line(&envelopes)
+    }
+
+    fn save_snapshot(&mut self, snapshot: &Snapshot<T>) -> io::Result<()> {
+        let bytes = serde_json::to_vec(snapshot).map_err(invalid_data)?;
+        let tmp = self.dir.join(SNAPSHOT_TMP_FILE);
+        let mut file = File::create(&tmp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp, self.dir.join(SNAPSHOT_FILE))?;
+        self.sync_dir()?;
+
+        // The snapshot now covers everything in the log.
+        self.log.set_len(0)?;
+        self.log.sync_all()?;
+        self.poisoned = false;
+        Ok(())
+    }
+
+    fn load(&mut self) -> io::Result<Recovered<T>> {
+        let snapshot = match fs::read(self.dir.join(SNAPSHOT_FILE)) {
+            Ok(bytes) => Some(serde_json::from_slice(&bytes).map_err(invalid_data)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error),
+        };
+
+        let bytes = fs::read(self.dir.join(LOG_FILE))?;
+
+        // Anything after the last newline is an append that was cut short.
+        let complete = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        if complete < bytes.len() {
+            self.log.set_len(complete as u64)?;
+            self.log.sync_all()?;
+        }
+
+        let mut ops = Vec::new();
+        for line in bytes[..complete].split(|&b| b == b'\n') {
+            if line.is_empty() {
+                continue;
+            }
+            let envelopes: Vec<Envelope<T>> = if line.starts_with(b"[") {
+                serde_json::from_slice(line).map_err(invalid_data)?
+            } else {
+                vec![serde_json::from_slice(line).map_err(invalid_data)?]
+            };
+            for envelope in envelopes {
+                if envelope.version != FORMAT_VERSION {
+                    return Err(invalid_data(format!(
+                        "unsupported log format version {}",
+                        envelope.version
+                    )));
+                }
+                ops.push(envelope.into_parts());
+            }
+        }
+        Ok((snapshot, ops))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, ApplyOutcome, Replica};
+    use crate::or_set::{self, ORSet};
+    use crate::storage::PersistentReplica;
+    use std::collections::BTreeSet;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    type Persistent = PersistentReplica<ORSet<u32>, FileStorage>;
+
+    fn open(dir: &Path) -> Persistent {
+        let replica = Replica::new(ActorId(1), ORSet::default()).with_causal_delivery();
+        PersistentReplica::open(replica, FileStorage::open(dir).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_restart_recovers_state_from_log() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let mut replica = open(dir.path());
+        let (_, first) = replica.apply(or_set::Op::Add(1)).unwrap();
+        replica.apply(or_set::Op::Add(2)).unwrap();
+        let before = replica.replica().clone();
+        drop(replica);
+
+        // Act
+        let mut restarted = open(dir.path());
+
+        // Assert: the state is back, and new ops do not reuse old dots.
+        assert_eq!(restarted.replica().state(), before.state());
+        assert_eq!(restarted.replica().clock(), before.clock());
+        let (_, ctx) = restarted.apply(or_set::Op::Rm(1)).unwrap();
+        assert!(ctx.dot.counter > before.clock().get(&ActorId(1)));
+        assert_ne!(ctx.dot, first.dot);
+    }
+
+    #[test]
+    fn test_restart_recovers_from_snapshot_and_log_tail() {
+        // Arrange: a snapshot is taken every two ops, so one op stays in the log.
+        let dir = tempfile::tempdir().unwrap();
+        let mut replica = open(dir.path()).with_snapshot_every(2);
+        for value in 1..=3 {
+            replica.apply(or_set::Op::Add(value)).unwrap();
+        }
+        let before = replica.replica().clone();
+        drop(replica);
+
+        // Act
+        let restarted = open(dir.path());
+
+        // Assert
+        assert!(dir.path().join(SNAPSHOT_FILE).exists());
+        let (snapshot, log) =
+            Storage::<ORSet<u32>>::load(&mut FileStorage::open(dir.path()).unwrap()).unwrap();
+        assert_eq!(
+            snapshot.map(|s| s.state.read()),
+            Some(BTreeSet::from([1, 2]))
+        );
+        assert_eq!(log.len(), 1);
+        assert_eq!(restarted.replica().state(), before.state());
+        assert_eq!(restarted.replica().applied(), before.applied());
+    }
+
+    #[test]
+    fn test_restart_keeps_buffered_ops() {
+        // Arrange: an op from a peer arrives before its predecessor.
+        let dir = tempfile::tempdir().unwrap();
+        let mut peer = Replica::new(ActorId(2), ORSet::default());
+        let (op1, ctx1) = peer.apply(or_set::Op::Add(1));
+        let (op2, ctx2) = peer.apply(or_set::Op::Add(2));
+        let mut replica = open(dir.path());
+        assert_eq!(
+            replica.apply_remote(op2, ctx2).unwrap(),
+            ApplyOutcome::Buffered
+        );
+        drop(replica);
+
+        // Act
+        let mut restarted = open(dir.path());
+        restarted.apply_remote(op1, ctx1).unwrap();
+
+        // Assert
+        assert_eq!(restarted.replica().read(), BTreeSet::from([1, 2]));
+    }
+
+    #[test]
+    fn test_torn_append_is_discarded() {
+        // Arrange: a crash cut the last append short.
+        let dir = tempfile::tempdir().unwrap();
+        let mut replica = open(dir.path());
+        replica.apply(or_set::Op::Add(1)).unwrap();
+        drop(replica);
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join(LOG_FILE))
+            .unwrap();
+        log.write_all(br#"{"version":1,"op":{"Add":"#).unwrap();
+        drop(log);
+
+        // Act
+        let mut restarted = open(dir.path());
+        restarted.apply(or_set::Op::Add(2)).unwrap();
+        drop(restarted);
+
+        // Assert: the partial line is gone and later appends are readable.
+        let restarted = open(dir.path());
+        assert_eq!(restarted.replica().read(), BTreeSet::from([1, 2]));
+    }
+
+    #[test]
+    fn test_restart_recovers_dot_keyed_state() {
+        use crate::g_counter::{self, GCounter};
+
+        // Arrange: a state keyed by dots, snapshotted after two increments.
+        let dir = tempfile::tempdir().unwrap();
+        let open = || {
+            let replica = Replica::new(ActorId(1), GCounter::default());
+            PersistentReplica::open(replica, FileStorage::open(dir.path()).unwrap()).unwrap()
+        };
+        let mut replica = open().with_snapshot_every(2);
+        for _ in 0..3 {
+            replica.apply(g_counter::Op::Inc(1)).unwrap();
+        }
+        let before = replica.replica().clone();
+        drop(replica);
+
+        // Act
+        let restarted = open();
+
+        // Assert
+        assert!(dir.path().join(SNAPSHOT_FILE).exists());
+        assert_eq!(restarted.replica().read(), 3);
+        assert_eq!(restarted.replica().state(), before.state());
+    }
+
+    #[test]
+    fn test_refused_op_is_not_logged() {
+        use crate::Error;
+        use crate::bounded_counter::{self, BoundedCounter};
+        use crate::storage::PersistError;
+
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let open = || {
+            let replica = Replica::new(ActorId(1), BoundedCounter::default());
+            PersistentReplica::open(replica, FileStorage::open(dir.path()).unwrap()).unwrap()
+        };
+        let mut replica = open();
+        replica.apply(bounded_counter::Op::Inc(5)).unwrap();
+
+        // Act
+        let refused = replica.apply(bounded_counter::Op::Dec(6));
+        drop(replica);
+
+        // Assert
+        assert!(matches!(
+            refused,
+            Err(PersistError::Invalid(Error::InsufficientRights(ActorId(1))))
+        ));
+        let restarted = open();
+        assert_eq!(restarted.replica().read(), 5);
+        assert_eq!(restarted.replica().clock().get(&ActorId(1)), 1);
+    }
+
+    #[test]
+    fn test_restart_recovers_whole_transaction() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let mut replica = open(dir.path());
+        let batch = replica
+            .transaction(|tx| {
+                tx.apply(or_set::Op::Add(1))?;
+                tx.apply(or_set::Op::Add(2))?;
+                Ok(())
+            })
+            .unwrap()
+            .unwrap();
+        drop(replica);
+
+        // Act
+        let restarted = open(dir.path());
+
+        // Assert: the batch was written as a single line.
+        let log = fs::read_to_string(dir.path().join(LOG_FILE)).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        assert_eq!(restarted.replica().read(), BTreeSet::from([1, 2]));
+        assert_eq!(restarted.replica().clock(), &batch.ctx.clock);
+    }
+
+    /// A log file that fails its next write partway through, its next sync, or
+    /// its next truncation, as requested.
+    #[derive(Debug)]
+    struct FailingLog {
+        file: File,
+        write_fails_after: Option<usize>,
+        sync_fails: AtomicBool,
+        set_len_fails: AtomicBool,
+    }
+
+    impl FailingLog {
+        fn open(dir: &Path) -> Self {
+            let file = OpenOptions::new()
+                .append(true)
+                .open(dir.join(LOG_FILE))
+                .unwrap();
+            Self {
+                file,
+                write_fails_after: None,
+                sync_fails: AtomicBool::new(false),
+                set_len_fails: AtomicBool::new(false),
+            }
+        }
+    }
+
+    impl Write for FailingLog {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            match self.write_fails_after.take() {
+                Some(written) => {
+                    self.file.write_all(&buf[..written.min(buf.len())])?;
+                    Err(io::Error::other("no space left on device"))
+                }
+                None => self.file.write(buf),
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.file.flush()
+        }
+    }
+
+    impl LogFile for FailingLog {
+        fn len(&self) -> io::Result<u64> {
+            LogFile::len(&self.file)
+        }
+
+        fn set_len(&self, len: u64) -> io::Result<()> {
+            if self.set_len_fails.swap(false, Ordering::SeqCst) {
+                return Err(io::Error::other("input/output error"));
+            }
+            self.file.set_len(len)
+        }
+
+        fn sync_data(&self) -> io::Result<()> {
+            if self.sync_fails.swap(false, Ordering::SeqCst) {
+                return Err(io::Error::other("input/output error"));
+            }
+            self.file.sync_data()
+        }
+
+        fn sync_all(&self) -> io::Result<()> {
+            self.file.sync_all()
+        }
+    }
+
+    /// Opens a replica whose log is written through the given failing file.
+    fn open_failing(dir: &Path, configure: impl FnOnce(&mut FailingLog)) -> Persistent {
+        let mut storage = FileStorage::open(dir).unwrap();
+        let mut log = FailingLog::open(dir);
+        configure(&mut log);
+        storage.log = Box::new(log);
+        let replica = Replica::new(ActorId(1), ORSet::default()).with_causal_delivery();
+        PersistentReplica::open(replica, storage).unwrap()
+    }
+
+    #[test]
+    fn test_failed_write_is_cut_off_the_log() {
+        // Arrange: the first append stops halfway through its line.
+        let dir = tempfile::tempdir().unwrap();
+        let mut replica = open_failing(dir.path(), |log| log.write_fails_after = Some(10));
+
+        // Act
+        let failed = replica.apply(or_set::Op::Add(1));
+        let (_, ctx) = replica.apply(or_set::Op::Add(2)).unwrap();
+        drop(replica);
+
+        // Assert: the next append starts on its own line and the log loads.
+        assert!(failed.is_err());
+        assert_eq!(ctx.dot.counter, 1);
+        let restarted = open(dir.path());
+        assert_eq!(restarted.replica().read(), BTreeSet::from([2]));
+    }
+
+    #[test]
+    fn test_failed_sync_is_cut_off_the_log() {
+        // Arrange: the first append is written in full, but not synced.
+        let dir = tempfile::tempdir().unwrap();
+        let mut replica = open_failing(dir.path(), |log| *log.sync_fails.get_mut() = true);
+
+        // Act: the next op is given the dot the failed one was logged with.
+        let failed = replica.apply(or_set::Op::Add(1));
+        let (_, ctx) = replica.apply(or_set::Op::Add(2)).unwrap();
+        drop(replica);
+
+        // Assert: only the acknowledged op is logged under that dot.
+        assert!(failed.is_err());
+        assert_eq!(ctx.dot.counter, 1);
+        let (_, log) =
+            Storage::<ORSet<u32>>::load(&mut FileStorage::open(dir.path()).unwrap()).unwrap();
+        assert_eq!(log, vec![(or_set::Op::Add(2), ctx)]);
+    }
+
+    #[test]
+    fn test_log_that_cannot_be_cut_refuses_appends_until_snapshot() {
+        // Arrange: the append fails partway, and so does truncating it.
+        let dir = tempfile::tempdir().unwrap();
+        let mut replica = open_failing(dir.path(), |log| {
+            log.write_fails_after = Some(10);
+            *log.set_len_fails.get_mut() = true;
+        });
+        assert!(replica.apply(or_set::Op::Add(1)).is_err());
+
+        // Act
+        let refused = replica.apply(or_set::Op::Add(2));
+        replica.snapshot().unwrap();
+        let accepted = replica.apply(or_set::Op::Add(3));
+        drop(replica);
+
+        // Assert
+        assert!(refused.is_err());
+        assert!(accepted.is_ok());
+        let restarted = open(dir.path());
+        assert_eq!(restarted.replica().read(), BTreeSet::from([3]));
+    }
+
+    #[test]
+    fn test_corrupt_log_line_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(LOG_FILE), "not json\n").unwrap();
+
+        let mut storage = FileStorage::open(dir.path()).unwrap();
+        let error = Storage::<ORSet<u32>>::load(&mut storage).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
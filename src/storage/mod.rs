@@ -0,0 +1,467 @@
+#[cfg(feature = "file-storage")]
+mod file;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "file-storage")]
+pub use file::FileStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+
+use std::fmt;
+
+use crate::Error;
+use crate::core::{
+    ActorId, AddCtx, AppliedDots, ApplyOutcome, Batch, CmRDT, DeltaCmRDT, Ops, Replica, Snapshot,
+    Transaction, VClock,
+};
+
+/// The default number of logged operations between two snapshots.
+pub const DEFAULT_SNAPSHOT_EVERY: usize = 1000;
+
+/// The latest snapshot, if any, and the operations logged after it.
+pub type Recovered<T> = (Option<Snapshot<T>>, Ops<T>);
+
+/// Durable storage for the operations and snapshots of a single replica.
+///
+/// A storage keeps the latest [`Snapshot`] and a log of every operation applied
+/// since. Saving a snapshot may discard the operations logged before it.
+pub trait Storage<T: CmRDT> {
+    type Error;
+
+    /// Durably appends an operation to the log.
+    fn append(&mut self, op: &T::Op, ctx: &AddCtx) -> Result<(), Self::Error>;
+
+    /// Durably appends several operations to the log, all or nothing, such as
+    /// the operations of a [`Batch`].
+    fn append_batch(&mut self, ops: &[(&T::Op, &AddCtx)]) -> Result<(), Self::Error>;
+
+    /// Durably replaces the latest snapshot. Operations logged before it no
+    /// longer need to be kept.
+    fn save_snapshot(&mut self, snapshot: &Snapshot<T>) -> Result<(), Self::Error>;
+
+    /// Loads the latest snapshot, if any, and the operations logged after it in
+    /// the order they were appended.
+    fn load(&mut self) -> Result<Recovered<T>, Self::Error>;
+
+    /// Records that a state covering the clock was merged into the replica,
+    /// without the operations that produced it.
+    fn record_merge(&mut self, _clock: &VClock) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Returns the logged operations that a peer with the given applied dots
+    /// has not applied, in the order they were appended, or `None` if some of
+    /// them are not kept, such as those of a merged state.
+    ///
+    /// By default, no operations are kept for peers, and they are caught up
+    /// with a delta.
+    fn ops_since(&self, _applied: &AppliedDots) -> Result<Option<Ops<T>>, Self::Error> {
+        Ok(None)
+    }
+}
+
+/// The errors returned by the methods of a [`PersistentReplica`] that check the
+/// operations they are given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PersistError<E> {
+    /// The operation was refused, as by the matching method of [`Replica`].
+    Invalid(Error),
+    /// The storage failed to write the operation.
+    Storage(E),
+}
+
+impl<E: fmt::Display> fmt::Display for PersistError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::Invalid(error) => write!(f, "invalid operation: {error}"),
+            PersistError::Storage(error) => write!(f, "storage error: {error}"),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for PersistError<E> {}
+
+impl<E> From<Error> for PersistError<E> {
+    fn from(error: Error) -> Self {
+        PersistError::Invalid(error)
+    }
+}
+
+/// A [`Replica`] that writes every operation it applies to a [`Storage`] before
+/// returning it, and can be recovered from that storage after a restart.
+///
+/// Every operation is appended to the log before it reaches the replica, so
+/// when the storage fails, the replica is left as it was and the error is
+/// returned. The whole replica is snapshotted once enough operations have been
+/// logged. Since a snapshot records the dots the replica has applied, an
+/// operation that is both in the snapshot and in the log, for example after a
+/// crash between the two writes, is only replayed once.
+pub struct PersistentReplica<T: CmRDT, S: Storage<T>> {
+    replica: Replica<T>,
+    storage: S,
+    snapshot_every: usize,
+    since_snapshot: usize,
+}
+
+impl<T, S> fmt::Debug for PersistentReplica<T, S>
+where
+    T: CmRDT + fmt::Debug,
+    T::Op: fmt::Debug,
+    S: Storage<T> + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PersistentReplica")
+            .field("replica", &self.replica)
+            .field("storage", &self.storage)
+            .field("snapshot_every", &self.snapshot_every)
+            .field("since_snapshot", &self.since_snapshot)
+            .finish()
+    }
+}
+
+impl<T: CmRDT + Clone, S: Storage<T>> PersistentReplica<T, S> {
+    /// Recovers a replica from the storage.
+    ///
+    /// The latest snapshot is restored into the given replica, which provides
+    /// the actor and configuration, and the operations logged after it are
+    /// replayed. An empty storage leaves the replica as it is.
+    pub fn open(mut replica: Replica<T>, mut storage: S) -> Result<Self, S::Error> {
+        let (snapshot, log) = storage.load()?;
+        if let Some(snapshot) = snapshot {
+            replica.restore(snapshot);
+        }
+
+        let since_snapshot = log.len();
+        for (op, ctx) in log {
+            replica.replay(op, ctx);
+        }
+
+        Ok(Self {
+            replica,
+            storage,
+            snapshot_every: DEFAULT_SNAPSHOT_EVERY,
+            since_snapshot,
+        })
+    }
+
+    /// Sets the number of logged operations after which a snapshot is taken.
+    pub fn with_snapshot_every(mut self, operations: usize) -> Self {
+        self.snapshot_every = operations.max(1);
+        self
+    }
+
+    /// Checks, logs and applies a local operation, like [`Replica::try_apply`].
+    ///
+    /// The operation must only be sent to peers once this returns successfully.
+    /// If it is refused or logging fails, nothing is applied and no dot is
+    /// consumed.
+    pub fn apply(&mut self, op: T::Op) -> Result<(T::Op, AddCtx), PersistError<S::Error>> {
+        let ctx = self.replica.prepare(&op)?;
+        self.log(&[(&op, &ctx)]).map_err(PersistError::Storage)?;
+        self.replica.commit(op.clone(), ctx.clone());
+        Ok((op, ctx))
+    }
+
+    /// Applies a group of operations locally, like [`Replica::transaction`],
+    /// and logs them all or none before they reach the replica.
+    pub fn transaction<F>(&mut self, f: F) -> Result<Option<Batch<T>>, PersistError<S::Error>>
+    where
+        F: FnOnce(&mut Transaction<T>) -> Result<(), Error>,
+    {
+        let mut tx = self.replica.begin_transaction();
+        f(&mut tx)?;
+        let (crdt, clock, Some(batch)) = tx.into_parts() else {
+            return Ok(None);
+        };
+
+        let contexts: Vec<_> = batch.contexts().collect();
+        let ops: Vec<_> = batch.ops.iter().zip(&contexts).collect();
+        self.log(&ops).map_err(PersistError::Storage)?;
+        self.replica.commit_transaction(crdt, clock, &batch);
+        Ok(Some(batch))
+    }
+
+    /// Logs a remote operation and applies it, like [`Replica::apply_remote`].
+    ///
+    /// Duplicates are not logged. Buffered operations are, so that they are
+    /// still waiting for their predecessors after a restart.
+    pub fn apply_remote(&mut self, op: T::Op, ctx: AddCtx) -> Result<ApplyOutcome, S::Error> {
+        if !self.replica.has_received(&ctx.dot) {
+            self.log(&[(&op, &ctx)])?;
+        }
+        Ok(self.replica.apply_remote(op, ctx))
+    }
+
+    /// Validates a remote operation like [`Replica::try_apply_remote`], and
+    /// logs and applies it if it is valid.
+    pub fn try_apply_remote(
+        &mut self,
+        op: T::Op,
+        ctx: AddCtx,
+    ) -> Result<ApplyOutcome, PersistError<S::Error>> {
+        self.replica.check_remote(&op, &ctx)?;
+        self.apply_remote(op, ctx).map_err(PersistError::Storage)
+    }
+
+    /// Logs the new operations of a remote batch, all or none, and applies the
+    /// batch like [`Replica::apply_remote_batch`].
+    pub fn apply_remote_batch(&mut self, batch: Batch<T>) -> Result<ApplyOutcome, S::Error> {
+        self.log_remote_ops(batch.into_ops())
+    }
+
+    /// Validates every operation of a remote batch like
+    /// [`Replica::try_apply_remote_batch`], and logs and applies the batch if
+    /// all of them are valid.
+    pub fn try_apply_remote_batch(
+        &mut self,
+        batch: Batch<T>,
+    ) -> Result<ApplyOutcome, PersistError<S::Error>> {
+        let ops = batch.into_ops();
+        for (op, ctx) in &ops {
+            self.replica.check_remote(op, ctx)?;
+        }
+        self.log_remote_ops(ops).map_err(PersistError::Storage)
+    }
+
+    /// Merges a remote state, like [`Replica::merge`], and snapshots the result,
+    /// since a merge cannot be replayed from the log.
+    ///
+    /// The merge is only kept once the snapshot is saved.
+    pub fn merge(&mut self, remote_crdt: T, remote_clock: VClock) -> Result<(), S::Error> {
+        self.storage.record_merge(&remote_clock)?;
+        let mut next = self.replica.clone();
+        next.merge(remote_crdt, remote_clock);
+        self.save(next)
+    }
+
+    /// Records that the peer has observed every event covered by the clock, like
+    /// [`Replica::observe_peer`]. It is kept in memory until the next snapshot.
+    pub fn observe_peer(&mut self, actor: ActorId, clock: VClock) {
+        self.replica.observe_peer(actor, clock);
+    }
+
+    /// Saves a snapshot of the replica, after which the log can be discarded.
+    pub fn snapshot(&mut self) -> Result<(), S::Error> {
+        self.storage.save_snapshot(&self.replica.snapshot())?;
+        self.since_snapshot = 0;
+        Ok(())
+    }
+
+    /// Returns the underlying replica.
+    pub fn replica(&self) -> &Replica<T> {
+        &self.replica
+    }
+
+    /// Returns the underlying storage.
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Returns the underlying storage mutably, for maintenance that does not
+    /// change the replica.
+    pub fn storage_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// Durably appends operations that are about to be applied.
+    ///
+    /// Once enough operations have been logged, a snapshot of the replica as
+    /// it is before these operations is saved first. If it fails, the next
+    /// attempt waits for another `snapshot_every` operations, so a storage that
+    /// cannot snapshot does not refuse every later operation.
+    fn log(&mut self, ops: &[(&T::Op, &AddCtx)]) -> Result<(), S::Error> {
+        if self.since_snapshot >= self.snapshot_every
+            && let Err(error) = self.snapshot()
+        {
+            self.since_snapshot = 0;
+            return Err(error);
+        }
+
+        match ops {
+            [(op, ctx)] => self.storage.append(op, ctx)?,
+            ops => self.storage.append_batch(ops)?,
+        }
+        self.since_snapshot += ops.len();
+        Ok(())
+    }
+
+    /// Logs the operations of a remote batch that have not been received yet,
+    /// and applies the whole batch.
+    fn log_remote_ops(&mut self, ops: Vec<(T::Op, AddCtx)>) -> Result<ApplyOutcome, S::Error> {
+        let new: Vec<_> = ops
+            .iter()
+            .filter(|(_, ctx)| !self.replica.has_received(&ctx.dot))
+            .map(|(op, ctx)| (op, ctx))
+            .collect();
+        if !new.is_empty() {
+            self.log(&new)?;
+        }
+        Ok(self.replica.apply_remote_ops(ops))
+    }
+
+    /// Saves a snapshot of the given replica, and replaces this one with it.
+    fn save(&mut self, replica: Replica<T>) -> Result<(), S::Error> {
+        self.storage.save_snapshot(&replica.snapshot())?;
+        self.replica = replica;
+        self.since_snapshot = 0;
+        Ok(())
+    }
+}
+
+impl<T: DeltaCmRDT + Clone, S: Storage<T>> PersistentReplica<T, S> {
+    /// Merges a delta produced by a peer's [`Replica::delta_since`], like
+    /// [`Replica::merge_delta`], and snapshots the result.
+    pub fn merge_delta(&mut self, delta: T, clock: VClock) -> Result<(), S::Error> {
+        self.storage.record_merge(&clock)?;
+        let mut next = self.replica.clone();
+        next.merge_delta(delta, clock);
+        self.save(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ActorId;
+    use crate::or_set::{self, ORSet};
+    use std::collections::BTreeSet;
+
+    /// A storage that keeps everything in memory, and fails every write while
+    /// `failing` is set.
+    #[derive(Debug, Default)]
+    struct FlakyStorage {
+        failing: bool,
+        snapshot: Option<Snapshot<ORSet<u32>>>,
+        log: Vec<(or_set::Op<u32>, AddCtx)>,
+    }
+
+    impl FlakyStorage {
+        fn check(&self) -> Result<(), &'static str> {
+            if self.failing {
+                Err("write failed")
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl Storage<ORSet<u32>> for FlakyStorage {
+        type Error = &'static str;
+
+        fn append(&mut self, op: &or_set::Op<u32>, ctx: &AddCtx) -> Result<(), Self::Error> {
+            self.check()?;
+            self.log.push((op.clone(), ctx.clone()));
+            Ok(())
+        }
+
+        fn append_batch(&mut self, ops: &[(&or_set::Op<u32>, &AddCtx)]) -> Result<(), Self::Error> {
+            self.check()?;
+            let ops = ops.iter().map(|&(op, ctx)| (op.clone(), ctx.clone()));
+            self.log.extend(ops);
+            Ok(())
+        }
+
+        fn save_snapshot(&mut self, snapshot: &Snapshot<ORSet<u32>>) -> Result<(), Self::Error> {
+            self.check()?;
+            self.snapshot = Some(snapshot.clone());
+            self.log.clear();
+            Ok(())
+        }
+
+        fn load(&mut self) -> Result<Recovered<ORSet<u32>>, Self::Error> {
+            Ok((self.snapshot.clone(), self.log.clone()))
+        }
+    }
+
+    fn open() -> PersistentReplica<ORSet<u32>, FlakyStorage> {
+        let replica = Replica::new(ActorId(1), ORSet::default());
+        PersistentReplica::open(replica, FlakyStorage::default()).unwrap()
+    }
+
+    #[test]
+    fn test_failed_write_leaves_replica_untouched() {
+        // Arrange
+        let mut replica = open();
+        replica.apply(or_set::Op::Add(1)).unwrap();
+        let mut peer = Replica::new(ActorId(2), ORSet::default());
+        let (remote_op, remote_ctx) = peer.apply(or_set::Op::Add(2));
+        let before = replica.replica().clone();
+        replica.storage_mut().failing = true;
+
+        // Act
+        let local = replica.apply(or_set::Op::Add(3));
+        let remote = replica.apply_remote(remote_op.clone(), remote_ctx.clone());
+        let batch = replica.transaction(|tx| {
+            tx.apply(or_set::Op::Rm(1))?;
+            Ok(())
+        });
+        let merge = replica.merge(peer.state().clone(), peer.clock().clone());
+
+        // Assert: nothing reached the replica, so the ops can be retried.
+        assert_eq!(local, Err(PersistError::Storage("write failed")));
+        assert_eq!(remote, Err("write failed"));
+        assert_eq!(batch, Err(PersistError::Storage("write failed")));
+        assert_eq!(merge, Err("write failed"));
+        assert_eq!(replica.replica().state(), before.state());
+        assert_eq!(replica.replica().clock(), before.clock());
+        assert_eq!(replica.replica().applied(), before.applied());
+
+        replica.storage_mut().failing = false;
+        assert_eq!(
+            replica.apply_remote(remote_op, remote_ctx),
+            Ok(ApplyOutcome::Applied)
+        );
+        let (_, ctx) = replica.apply(or_set::Op::Add(3)).unwrap();
+        assert_eq!(ctx.dot.counter, 2);
+        assert_eq!(replica.storage().log.len(), 3);
+    }
+
+    #[test]
+    fn test_try_apply_remote_batch_logs_nothing_when_invalid() {
+        // Arrange: a copy of the batch whose context does not follow its
+        // previous counter.
+        let mut peer = Replica::new(ActorId(2), ORSet::default());
+        let batch = peer
+            .transaction(|tx| {
+                tx.apply(or_set::Op::Add(1))?;
+                tx.apply(or_set::Op::Add(2))?;
+                Ok(())
+            })
+            .unwrap()
+            .unwrap();
+        let mut invalid = batch.clone();
+        invalid.ctx.prev = invalid.ctx.dot.counter;
+        let mut replica = open();
+
+        // Act
+        let rejected = replica.try_apply_remote_batch(invalid);
+        let accepted = replica.try_apply_remote_batch(batch);
+
+        // Assert
+        assert!(matches!(rejected, Err(PersistError::Invalid(_))));
+        assert_eq!(accepted, Ok(ApplyOutcome::Applied));
+        assert_eq!(replica.storage().log.len(), 2);
+        assert_eq!(replica.replica().read(), BTreeSet::from([1, 2]));
+    }
+
+    #[test]
+    fn test_failed_snapshot_is_retried_later() {
+        // Arrange: the first op after two logged ops takes a snapshot.
+        let mut replica = open().with_snapshot_every(2);
+        replica.apply(or_set::Op::Add(1)).unwrap();
+        replica.apply(or_set::Op::Add(2)).unwrap();
+        replica.storage_mut().failing = true;
+
+        // Act
+        let failed = replica.apply(or_set::Op::Add(3));
+        replica.storage_mut().failing = false;
+        let retried = replica.apply(or_set::Op::Add(3));
+
+        // Assert: the next op is not refused for the missed snapshot.
+        assert_eq!(failed, Err(PersistError::Storage("write failed")));
+        assert!(retried.is_ok());
+        assert!(replica.storage().snapshot.is_none());
+        assert_eq!(replica.replica().read(), BTreeSet::from([1, 2, 3]));
+    }
+}
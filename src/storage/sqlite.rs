@@ -0,0 +1,490 @@
+use rusqlite::types::Type;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+use crate::core::{ActorId, AddCtx, AppliedDots, CmRDT, Dot, Ops, Snapshot, VClock};
+use crate::storage::{Recovered, Storage};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS replica (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        op_counter INTEGER NOT NULL,
+        clock TEXT NOT NULL,
+        applied TEXT NOT NULL,
+        peers TEXT NOT NULL,
+        pending TEXT NOT NULL,
+        state TEXT NOT NULL,
+        last_seq INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS ops (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        actor INTEGER NOT NULL,
+        counter INTEGER NOT NULL,
+        prev INTEGER NOT NULL,
+        op TEXT NOT NULL,
+        clock TEXT NOT NULL,
+        UNIQUE (actor, counter)
+    );
+    CREATE TABLE IF NOT EXISTS unlogged (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        clock TEXT NOT NULL
+    );
+";
+
+/// A [`Storage`] that keeps a replica in a SQLite database.
+///
+/// The latest snapshot is kept in the `replica` table, with the state, clock
+/// and other parts of the [`Snapshot`] stored as JSON columns. Every operation
+/// is inserted into the `ops` table, indexed by its `Dot`, in its own
+/// transaction, or in one transaction per batch, so it is committed before
+/// `PersistentReplica::apply` returns.
+///
+/// Unlike `FileStorage`, saving a snapshot keeps the operations, so that
+/// [`Storage::ops_since`] can send peers the operations they are missing.
+/// Operations that every peer has received can be removed with
+/// [`SqliteStorage::discard_ops`]. The clock of every operation
+/// that is not in the `ops` table, because it was discarded or merged as part
+/// of a state, is kept in the `unlogged` table, and a peer that has not applied
+/// all of them is caught up with a delta instead.
+///
+/// Actors and counters are stored as SQLite integers, so counters must stay
+/// below `2^63` to be compared correctly, as every counter generated by a
+/// `Replica` does.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    /// Opens the database at the given path, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Opens a database that only lives as long as the storage.
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Uses an existing connection, creating the tables if needed.
+    pub fn from_connection(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Removes the stored operations whose dots the clock contains, typically
+    /// the stable clock of the replica, once they are part of the latest
+    /// snapshot. Returns the number of operations removed.
+    pub fn discard_ops(&mut self, clock: &VClock) -> rusqlite::Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut removed = 0;
+        {
+            let mut stmt = tx.prepare(
+                "DELETE FROM ops WHERE actor = ?1 AND counter <= ?2
+                 AND seq <= (SELECT COALESCE(MAX(last_seq), 0) FROM replica)",
+            )?;
+            for (actor, counter) in &clock.0 {
+                removed += stmt.execute(params![actor.0 as i64, *counter as i64])?;
+            }
+        }
+        add_unlogged(&tx, clock)?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Returns every stored operation of a `T` replica that a peer with the
+    /// given clock has not seen, unless the clock does not cover some of the
+    /// operations missing from the `ops` table.
+    ///
+    /// Every dot the clock covers is taken as applied by the peer. Without
+    /// causal delivery, a clock may also cover operations the peer has only
+    /// heard of, which would then be left out: pass the peer's
+    /// `Replica::applied` to [`Storage::ops_since`] instead.
+    pub fn ops_since<T>(&self, clock: &VClock) -> rusqlite::Result<Option<Ops<T>>>
+    where
+        T: CmRDT + Serialize + DeserializeOwned,
+        T::Op: Serialize + DeserializeOwned,
+    {
+        Storage::<T>::ops_since(self, &AppliedDots::from(clock.clone()))
+    }
+}
+
+fn to_json<V: Serialize>(value: &V) -> rusqlite::Result<String> {
+    serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn from_json<V: DeserializeOwned>(row: &rusqlite::Row<'_>, column: usize) -> rusqlite::Result<V> {
+    let text: String = row.get(column)?;
+    serde_json::from_str(&text)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, Type::Text, Box::new(e)))
+}
+
+/// Returns the clock of the operations that are not in the `ops` table.
+fn unlogged(conn: &Connection) -> rusqlite::Result<VClock> {
+    let clock = conn
+        .query_row("SELECT clock FROM unlogged WHERE id = 0", [], |row| {
+            from_json(row, 0)
+        })
+        .optional()?;
+    Ok(clock.unwrap_or_default())
+}
+
+/// Adds the clock to that of the operations that are not in the `ops` table.
+fn add_unlogged(conn: &Connection, clock: &VClock) -> rusqlite::Result<()> {
+    let mut unlogged = unlogged(conn)?;
+    unlogged.merge(clock.clone());
+    conn.execute(
+        "INSERT OR REPLACE INTO unlogged (id, clock) VALUES (0, ?1)",
+        [to_json(&unlogged)?],
+    )?;
+    Ok(())
+}
+
+/// Inserts an operation into the `ops` table.
+fn insert_op<T>(conn: &Connection, op: &T::Op, ctx: &AddCtx) -> rusqlite::Result<()>
+where
+    T: CmRDT,
+    T::Op: Serialize,
+{
+    // An operation that is logged again, such as a redelivered buffered op,
+    // keeps its original position.
+    conn.execute(
+        "INSERT OR IGNORE INTO ops (actor, counter, prev, op, clock)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            ctx.dot.actor.0 as i64,
+            ctx.dot.counter as i64,
+            ctx.prev as i64,
+            to_json(op)?,
+            to_json(&ctx.clock)?,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Reads an operation from a row whose columns 1 to 5 are the actor, counter,
+/// prev, op and clock of the `ops` table.
+fn read_op<T>(row: &rusqlite::Row<'_>) -> rusqlite::Result<(T::Op, AddCtx)>
+where
+    T: CmRDT,
+    T::Op: DeserializeOwned,
+{
+    let dot = Dot {
+        actor: ActorId(row.get::<_, i64>(1)? as u64),
+        counter: row.get::<_, i64>(2)? as u64,
+    };
+    let prev = row.get::<_, i64>(3)? as u64;
+    let op = from_json(row, 4)?;
+    let clock = from_json(row, 5)?;
+    Ok((op, AddCtx { dot, prev, clock }))
+}
+
+impl<T> Storage<T> for SqliteStorage
+where
+    T: CmRDT + Serialize + DeserializeOwned,
+    T::Op: Serialize + DeserializeOwned,
+{
+    type Error = rusqlite::Error;
+
+    fn append(&mut self, op: &T::Op, ctx: &AddCtx) -> rusqlite::Result<()> {
+        insert_op::<T>(&self.conn, op, ctx)
+    }
+
+    fn append_batch(&mut self, ops: &[(&T::Op, &AddCtx)]) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        for (op, ctx) in ops {
+            insert_op::<T>(&tx, op, ctx)?;
+        }
+        tx.commit()
+    }
+
+    fn save_snapshot(&mut self, snapshot: &Snapshot<T>) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO replica
+                 (id, op_counter, clock, applied, peers, pending, state, last_seq)
+             VALUES (0, ?1, ?2, ?3, ?4, ?5, ?6, (SELECT COALESCE(MAX(seq), 0) FROM ops))",
+            params![
+                snapshot.op_counter as i64,
+                to_json(&snapshot.clock)?,
+                to_json(&snapshot.applied)?,
+                to_json(&snapshot.peers)?,
+                to_json(&snapshot.pending)?,
+                to_json(&snapshot.state)?,
+            ],
+        )?;
+        tx.commit()
+    }
+
+    fn record_merge(&mut self, clock: &VClock) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        add_unlogged(&tx, clock)?;
+        tx.commit()
+    }
+
+    /// Returns every stored operation the peer has not applied, unless it has
+    /// not applied some of the operations missing from the `ops` table.
+    fn ops_since(&self, applied: &AppliedDots) -> rusqlite::Result<Option<Ops<T>>> {
+        if !applied.covers(&unlogged(&self.conn)?) {
+            return Ok(None);
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT seq, actor, counter, prev, op, clock FROM ops ORDER BY seq")?;
+        let rows = stmt.query_map([], |row| {
+            let dot = Dot {
+                actor: ActorId(row.get::<_, i64>(1)? as u64),
+                counter: row.get::<_, i64>(2)? as u64,
+            };
+            // Only decode the operations the peer is missing.
+            if applied.contains(&dot) {
+                return Ok(None);
+            }
+            read_op::<T>(row).map(Some)
+        })?;
+
+        let mut ops = Vec::new();
+        for row in rows {
+            ops.extend(row?);
+        }
+        Ok(Some(ops))
+    }
+
+    fn load(&mut self) -> rusqlite::Result<Recovered<T>> {
+        let tx = self.conn.transaction()?;
+        let snapshot = tx
+            .query_row(
+                "SELECT op_counter, clock, applied, peers, pending, state, last_seq
+                 FROM replica WHERE id = 0",
+                [],
+                |row| {
+                    let snapshot = Snapshot {
+                        op_counter: row.get::<_, i64>(0)? as u64,
+                        clock: from_json(row, 1)?,
+                        applied: from_json(row, 2)?,
+                        peers: from_json(row, 3)?,
+                        pending: from_json(row, 4)?,
+                        state: from_json(row, 5)?,
+                    };
+                    Ok((snapshot, row.get::<_, i64>(6)?))
+                },
+            )
+            .optional()?;
+        let last_seq = snapshot.as_ref().map_or(0, |(_, seq)| *seq);
+
+        let ops = tx
+            .prepare(
+                "SELECT seq, actor, counter, prev, op, clock FROM ops
+                 WHERE seq > ?1 ORDER BY seq",
+            )?
+            .query_map([last_seq], read_op::<T>)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        tx.commit()?;
+
+        Ok((snapshot.map(|(snapshot, _)| snapshot), ops))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Replica;
+    use crate::or_set::{self, ORSet};
+    use crate::storage::PersistentReplica;
+    use std::collections::BTreeSet;
+
+    type Persistent = PersistentReplica<ORSet<u32>, SqliteStorage>;
+
+    fn open(storage: SqliteStorage) -> Persistent {
+        PersistentReplica::open(Replica::new(ActorId(1), ORSet::default()), storage).unwrap()
+    }
+
+    fn ops_since(
+        storage: &SqliteStorage,
+        applied: &AppliedDots,
+    ) -> Option<Vec<(or_set::Op<u32>, AddCtx)>> {
+        Storage::<ORSet<u32>>::ops_since(storage, applied).unwrap()
+    }
+
+    #[test]
+    fn test_restart_recovers_from_database_file() {
+        // Arrange: a snapshot is taken every two ops, so one op stays in the tail.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("replica.db");
+        let mut replica = open(SqliteStorage::open(&path).unwrap()).with_snapshot_every(2);
+        for value in 1..=3 {
+            replica.apply(or_set::Op::Add(value)).unwrap();
+        }
+        let before = replica.replica().clone();
+        drop(replica);
+
+        // Act
+        let mut restarted = open(SqliteStorage::open(&path).unwrap());
+
+        // Assert
+        assert_eq!(restarted.replica().state(), before.state());
+        assert_eq!(restarted.replica().clock(), before.clock());
+        let (_, ctx) = restarted.apply(or_set::Op::Add(4)).unwrap();
+        assert_eq!(ctx.dot.counter, 4);
+    }
+
+    #[test]
+    fn test_snapshot_keeps_ops_for_sync() {
+        let mut replica = open(SqliteStorage::open_in_memory().unwrap()).with_snapshot_every(2);
+        for value in 1..=3 {
+            replica.apply(or_set::Op::Add(value)).unwrap();
+        }
+
+        let (_, tail) = Storage::<ORSet<u32>>::load(replica.storage_mut()).unwrap();
+        let all = ops_since(replica.storage(), &AppliedDots::default()).unwrap();
+
+        assert_eq!(tail.len(), 1);
+        assert_eq!(all.len(), 3);
+    }
+
+    #[test]
+    fn test_ops_since_returns_missing_ops_in_order() {
+        // Arrange: ops from two actors, one of which the peer has partly seen.
+        let mut replica = open(SqliteStorage::open_in_memory().unwrap());
+        let mut remote = Replica::new(ActorId(2), ORSet::default());
+        let a1 = replica.apply(or_set::Op::Add(1)).unwrap();
+        let (op, ctx) = remote.apply(or_set::Op::Add(2));
+        replica.apply_remote(op.clone(), ctx.clone()).unwrap();
+        let a2 = replica.apply(or_set::Op::Rm(1)).unwrap();
+        let mut peer = AppliedDots::default();
+        peer.insert(a1.1.dot);
+        peer.insert(ctx.dot);
+
+        // Act
+        let missing = ops_since(replica.storage(), &peer).unwrap();
+        let everything = ops_since(replica.storage(), &AppliedDots::default()).unwrap();
+
+        // Assert
+        assert_eq!(missing, vec![a2.clone()]);
+        assert_eq!(everything, vec![a1, (op, ctx), a2]);
+    }
+
+    #[test]
+    fn test_ops_since_clock_returns_ops_after_it() {
+        // Arrange
+        let mut replica = open(SqliteStorage::open_in_memory().unwrap());
+        replica.apply(or_set::Op::Add(1)).unwrap();
+        let seen = replica.replica().clock().clone();
+        let a2 = replica.apply(or_set::Op::Add(2)).unwrap();
+
+        // Act
+        let missing = replica.storage().ops_since::<ORSet<u32>>(&seen).unwrap();
+
+        // Assert
+        assert_eq!(missing, Some(vec![a2]));
+    }
+
+    #[test]
+    fn test_ops_since_includes_ops_the_peer_only_heard_of() {
+        // Arrange: the peer's clock covers A's op through B, but it only
+        // applied B's op.
+        let mut replica = open(SqliteStorage::open_in_memory().unwrap());
+        let (a_op, a_ctx) = replica.apply(or_set::Op::Add(1)).unwrap();
+        let mut remote = Replica::new(ActorId(2), ORSet::default());
+        remote.apply_remote(a_op.clone(), a_ctx.clone());
+        let (b_op, b_ctx) = remote.apply(or_set::Op::Add(2));
+        replica.apply_remote(b_op.clone(), b_ctx.clone()).unwrap();
+
+        let mut peer = Replica::new(ActorId(3), ORSet::default());
+        peer.apply_remote(b_op, b_ctx);
+
+        // Act
+        let missing = ops_since(replica.storage(), peer.applied()).unwrap();
+
+        // Assert
+        assert_eq!(missing, vec![(a_op, a_ctx)]);
+    }
+
+    #[test]
+    fn test_restart_recovers_dot_keyed_state() {
+        use crate::g_counter::{self, GCounter};
+
+        // Arrange: a state keyed by dots, snapshotted after two increments.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("replica.db");
+        let open = || {
+            let replica = Replica::new(ActorId(1), GCounter::default());
+            PersistentReplica::open(replica, SqliteStorage::open(&path).unwrap()).unwrap()
+        };
+        let mut replica = open().with_snapshot_every(2);
+        for _ in 0..3 {
+            replica.apply(g_counter::Op::Inc(1)).unwrap();
+        }
+        let before = replica.replica().clone();
+        drop(replica);
+
+        // Act
+        let restarted = open();
+
+        // Assert
+        let (snapshot, tail) =
+            Storage::<GCounter>::load(&mut SqliteStorage::open(&path).unwrap()).unwrap();
+        assert_eq!(snapshot.map(|s| s.state.read()), Some(2));
+        assert_eq!(tail.len(), 1);
+        assert_eq!(restarted.replica().state(), before.state());
+    }
+
+    #[test]
+    fn test_discard_ops_keeps_ops_after_snapshot() {
+        // Arrange
+        let mut replica = open(SqliteStorage::open_in_memory().unwrap());
+        replica.apply(or_set::Op::Add(1)).unwrap();
+        replica.snapshot().unwrap();
+        replica.apply(or_set::Op::Add(2)).unwrap();
+        let clock = replica.replica().clock().clone();
+        let storage = replica.storage_mut();
+
+        // Act
+        let removed = storage.discard_ops(&clock).unwrap();
+
+        // Assert: only the op covered by the snapshot is removed.
+        assert_eq!(removed, 1);
+        let (snapshot, tail) = Storage::<ORSet<u32>>::load(storage).unwrap();
+        assert_eq!(snapshot.unwrap().state.read(), BTreeSet::from([1]));
+        assert_eq!(tail.len(), 1);
+        assert_eq!(ops_since(storage, &AppliedDots::default()), None);
+    }
+
+    #[test]
+    fn test_merged_state_is_not_sent_as_ops() {
+        // Arrange: a state merged from a peer, without its ops.
+        let mut replica = open(SqliteStorage::open_in_memory().unwrap());
+        let mut peer = Replica::new(ActorId(2), ORSet::default());
+        let (_, ctx) = peer.apply(or_set::Op::Add(2));
+        replica.apply(or_set::Op::Add(1)).unwrap();
+        replica
+            .merge(peer.state().clone(), peer.clock().clone())
+            .unwrap();
+
+        // Act
+        let fresh = ops_since(replica.storage(), &AppliedDots::default());
+        let mut applied = AppliedDots::default();
+        applied.insert(ctx.dot);
+        let caught_up = ops_since(replica.storage(), &applied);
+
+        // Assert: only a peer that applied the merged op can get the rest.
+        assert_eq!(fresh, None);
+        assert_eq!(caught_up.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_duplicate_append_is_ignored() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut replica = Replica::new(ActorId(1), ORSet::default());
+        let (op, ctx) = replica.apply(or_set::Op::Add(1));
+
+        Storage::<ORSet<u32>>::append(&mut storage, &op, &ctx).unwrap();
+        Storage::<ORSet<u32>>::append(&mut storage, &op, &ctx).unwrap();
+
+        let (_, ops) = Storage::<ORSet<u32>>::load(&mut storage).unwrap();
+        assert_eq!(ops, vec![(op, ctx)]);
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::{ActorId, AddCtx, AppliedDots, CmRDT, VClock};
+
+/// The current version of the sync protocol.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A message exchanged by two [`Session`](crate::sync::Session)s.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize, T::Op: Serialize",
+    deserialize = "T: Deserialize<'de>, T::Op: Deserialize<'de>"
+))]
+pub enum Message<T: CmRDT> {
+    /// Opens the session with every dot the sender has applied. Its clock is
+    /// not enough, as it may cover operations the sender has only heard of.
+    Hello {
+        version: u32,
+        actor: ActorId,
+        applied: AppliedDots,
+    },
+    /// Catches the receiver up with the operations it lacks, in the order the
+    /// sender applied them.
+    Ops(Vec<(T::Op, AddCtx)>),
+    /// Catches the receiver up with the part of the sender's state it lacks,
+    /// when the operations are no longer available.
+    Delta { state: T, clock: VClock },
+    /// A single operation, streamed once the session is live.
+    Op(T::Op, AddCtx),
+}
@@ -0,0 +1,280 @@
+//! A transport-agnostic protocol to bring two replicas in sync and keep them so.
+//!
+//! A [`Session`] holds the protocol state for one peer, and never touches the
+//! network itself: it turns the [`Message`]s received from the peer into
+//! updates of the local [`Replica`] and into messages to
+//! send back, which the caller delivers in order over any transport.
+//!
+//! The protocol runs in three steps:
+//!
+//! 1. Both sides send a [`Message::Hello`] with the dots they have applied.
+//! 2. On receiving the peer's hello, each side sends what the peer lacks: the
+//!    missing operations if it still has all of them, or a delta of its state
+//!    otherwise.
+//! 3. From then on, every operation applied locally is streamed as a
+//!    [`Message::Op`].
+//!
+//! The replica a session keeps in sync is a [`SyncTarget`]: either a
+//! [`LoggedReplica`], an in-memory replica with an [`OpLog`], or a
+//! [`PersistentReplica`], which logs to its storage and catches peers up from
+//! it.
+
+mod message;
+mod session;
+
+pub use crate::core::Ops;
+pub use message::{Message, PROTOCOL_VERSION};
+pub use session::{Phase, Session, SyncError};
+
+use std::convert::Infallible;
+use std::fmt;
+
+use crate::Error;
+use crate::core::{
+    ActorId, AddCtx, AppliedDots, ApplyOutcome, CmRDT, DeltaCmRDT, InfallibleLocal, Replica, VClock,
+};
+use crate::storage::{PersistError, PersistentReplica, Storage};
+
+/// A replica that a [`Session`] keeps in sync with a peer, along with the
+/// operations it can send to catch the peer up.
+pub trait SyncTarget<T: DeltaCmRDT> {
+    /// The error returned when the operations cannot be read or written.
+    type Error;
+
+    /// Returns the replica being kept in sync.
+    fn replica(&self) -> &Replica<T>;
+
+    /// Records that the peer has applied every event covered by the clock.
+    fn observe_peer(&mut self, actor: ActorId, clock: VClock);
+
+    /// Returns the operations that a peer with the given applied dots has not
+    /// applied, in the order they were applied, or `None` if some of them are
+    /// not kept.
+    fn ops_since(&self, applied: &AppliedDots) -> Result<Option<Ops<T>>, Self::Error>;
+
+    /// Validates and applies an operation received from the peer, like
+    /// [`Replica::try_apply_remote`].
+    fn try_apply_remote(
+        &mut self,
+        op: T::Op,
+        ctx: AddCtx,
+    ) -> Result<ApplyOutcome, SyncError<Self::Error>>;
+
+    /// Merges a delta received from the peer, like [`Replica::merge_delta`].
+    fn merge_delta(&mut self, delta: T, clock: VClock) -> Result<(), Self::Error>;
+}
+
+/// The operations a replica has applied, kept to catch peers up.
+///
+/// Every operation that reaches the replica's state must be recorded, in the
+/// order it does. A [`LoggedReplica`] takes care of that, including for the
+/// buffered operations that are only applied once their predecessors arrive.
+pub trait OpLog<T: CmRDT> {
+    /// Records an operation applied to the replica.
+    fn record(&mut self, op: &T::Op, ctx: &AddCtx);
+
+    /// Records that a state covering the clock was merged into the replica,
+    /// without the operations that produced it.
+    fn record_merge(&mut self, clock: &VClock);
+
+    /// Returns the operations that a peer with the given applied dots has not
+    /// applied, in the order they were applied, or `None` if some of them are
+    /// not kept.
+    fn ops_since(&self, applied: &AppliedDots) -> Option<Ops<T>>;
+}
+
+/// An [`OpLog`] that keeps no operations, so that peers are always caught up
+/// with a delta.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoLog;
+
+impl<T: CmRDT> OpLog<T> for NoLog {
+    fn record(&mut self, _op: &T::Op, _ctx: &AddCtx) {}
+
+    fn record_merge(&mut self, _clock: &VClock) {}
+
+    fn ops_since(&self, _applied: &AppliedDots) -> Option<Ops<T>> {
+        None
+    }
+}
+
+/// An in-memory [`OpLog`] that keeps every operation recorded in it.
+///
+/// Merged states leave gaps in the log, so only peers that have applied
+/// everything merged so far are caught up from it.
+#[derive(Debug, Clone)]
+pub struct MemoryLog<T: CmRDT> {
+    ops: Vec<(T::Op, AddCtx)>,
+    merged: VClock,
+}
+
+impl<T: CmRDT> Default for MemoryLog<T> {
+    fn default() -> Self {
+        Self {
+            ops: Vec::new(),
+            merged: VClock::default(),
+        }
+    }
+}
+
+impl<T: CmRDT> OpLog<T> for MemoryLog<T> {
+    fn record(&mut self, op: &T::Op, ctx: &AddCtx) {
+        self.ops.push((op.clone(), ctx.clone()));
+    }
+
+    fn record_merge(&mut self, clock: &VClock) {
+        self.merged.merge(clock.clone());
+    }
+
+    fn ops_since(&self, applied: &AppliedDots) -> Option<Ops<T>> {
+        if !applied.covers(&self.merged) {
+            return None;
+        }
+        let missing = self
+            .ops
+            .iter()
+            .filter(|(_, ctx)| !applied.contains(&ctx.dot))
+            .cloned()
+            .collect();
+        Some(missing)
+    }
+}
+
+/// An in-memory [`Replica`] that records every operation applied to it in an
+/// [`OpLog`].
+#[derive(Clone)]
+pub struct LoggedReplica<T: CmRDT, L> {
+    pub replica: Replica<T>,
+    pub log: L,
+}
+
+impl<T, L> fmt::Debug for LoggedReplica<T, L>
+where
+    T: CmRDT + fmt::Debug,
+    T::Op: fmt::Debug,
+    L: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoggedReplica")
+            .field("replica", &self.replica)
+            .field("log", &self.log)
+            .finish()
+    }
+}
+
+impl<T: CmRDT, L: OpLog<T>> LoggedReplica<T, L> {
+    pub fn new(replica: Replica<T>, log: L) -> Self {
+        Self { replica, log }
+    }
+
+    /// Applies and records a local operation, like [`Replica::try_apply`].
+    pub fn try_apply(&mut self, op: T::Op) -> Result<(T::Op, AddCtx), Error> {
+        let (op, ctx) = self.replica.try_apply(op)?;
+        self.log.record(&op, &ctx);
+        Ok((op, ctx))
+    }
+
+    /// Applies a remote operation like [`Replica::apply_remote`], and records
+    /// it once it is applied, along with any buffered operation it releases.
+    pub fn apply_remote(&mut self, op: T::Op, ctx: AddCtx) -> ApplyOutcome {
+        let log = &mut self.log;
+        self.replica
+            .apply_remote_reporting(op, ctx, &mut |op, ctx| log.record(op, ctx))
+    }
+
+    /// Merges a remote state like [`Replica::merge`], and records the merge
+    /// along with any buffered operation it releases.
+    pub fn merge(&mut self, remote_crdt: T, remote_clock: VClock) {
+        let log = &mut self.log;
+        log.record_merge(&remote_clock);
+        self.replica
+            .merge_reporting(remote_crdt, remote_clock, &mut |op, ctx| {
+                log.record(op, ctx)
+            });
+    }
+}
+
+impl<T: InfallibleLocal, L: OpLog<T>> LoggedReplica<T, L> {
+    /// Applies and records a local operation, like [`Replica::apply`].
+    pub fn apply(&mut self, op: T::Op) -> (T::Op, AddCtx) {
+        let (op, ctx) = self.replica.apply(op);
+        self.log.record(&op, &ctx);
+        (op, ctx)
+    }
+}
+
+impl<T: DeltaCmRDT, L: OpLog<T>> SyncTarget<T> for LoggedReplica<T, L> {
+    type Error = Infallible;
+
+    fn replica(&self) -> &Replica<T> {
+        &self.replica
+    }
+
+    fn observe_peer(&mut self, actor: ActorId, clock: VClock) {
+        self.replica.observe_peer(actor, clock);
+    }
+
+    fn ops_since(&self, applied: &AppliedDots) -> Result<Option<Ops<T>>, Infallible> {
+        Ok(self.log.ops_since(applied))
+    }
+
+    fn try_apply_remote(
+        &mut self,
+        op: T::Op,
+        ctx: AddCtx,
+    ) -> Result<ApplyOutcome, SyncError<Infallible>> {
+        self.replica.check_remote(&op, &ctx)?;
+        Ok(self.apply_remote(op, ctx))
+    }
+
+    fn merge_delta(&mut self, delta: T, clock: VClock) -> Result<(), Infallible> {
+        self.merge(delta, clock);
+        Ok(())
+    }
+}
+
+impl<E> From<PersistError<E>> for SyncError<E> {
+    fn from(error: PersistError<E>) -> Self {
+        match error {
+            PersistError::Invalid(error) => SyncError::Invalid(error),
+            PersistError::Storage(error) => SyncError::Storage(error),
+        }
+    }
+}
+
+impl<T: DeltaCmRDT + Clone, S: Storage<T>> SyncTarget<T> for PersistentReplica<T, S> {
+    type Error = S::Error;
+
+    fn replica(&self) -> &Replica<T> {
+        PersistentReplica::replica(self)
+    }
+
+    /// Records the peer's clock in memory, until the next snapshot.
+    fn observe_peer(&mut self, actor: ActorId, clock: VClock) {
+        PersistentReplica::observe_peer(self, actor, clock);
+    }
+
+    /// Returns the operations from [`Storage::ops_since`], without those that
+    /// are still buffered for causal delivery.
+    fn ops_since(&self, applied: &AppliedDots) -> Result<Option<Ops<T>>, S::Error> {
+        let ops = self.storage().ops_since(applied)?;
+        let replica = PersistentReplica::replica(self);
+        Ok(ops.map(|ops| {
+            ops.into_iter()
+                .filter(|(_, ctx)| replica.applied().contains(&ctx.dot))
+                .collect()
+        }))
+    }
+
+    fn try_apply_remote(
+        &mut self,
+        op: T::Op,
+        ctx: AddCtx,
+    ) -> Result<ApplyOutcome, SyncError<S::Error>> {
+        Ok(PersistentReplica::try_apply_remote(self, op, ctx)?)
+    }
+
+    fn merge_delta(&mut self, delta: T, clock: VClock) -> Result<(), S::Error> {
+        PersistentReplica::merge_delta(self, delta, clock)
+    }
+}
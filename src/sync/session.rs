@@ -0,0 +1,454 @@
+use std::convert::Infallible;
+use std::fmt;
+
+use crate::Error;
+use crate::core::{ActorId, AddCtx, DeltaCmRDT, Replica};
+use crate::sync::{Message, PROTOCOL_VERSION, SyncTarget};
+
+/// How far a [`Session`] has progressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Nothing has been sent or received yet.
+    Idle,
+    /// Our hello was sent, and we are waiting for the peer's.
+    AwaitingHello,
+    /// We have sent the peer what it lacked and are streaming our operations,
+    /// but have not received what we lacked yet.
+    CatchingUp,
+    /// Both sides are caught up, and operations are streamed both ways.
+    Live,
+}
+
+/// The errors that end a [`Session`], where `E` is the error of its
+/// [`SyncTarget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncError<E = Infallible> {
+    /// The peer speaks another version of the protocol.
+    IncompatibleVersion(u32),
+    /// The message is not allowed in the current phase of the session.
+    UnexpectedMessage(Phase),
+    /// The peer sent an operation that failed validation.
+    Invalid(Error),
+    /// The target failed to read or write operations.
+    Storage(E),
+}
+
+impl<E: fmt::Display> fmt::Display for SyncError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::IncompatibleVersion(version) => {
+                write!(f, "peer uses protocol version {version}")
+            }
+            SyncError::UnexpectedMessage(phase) => {
+                write!(f, "unexpected message in phase {phase:?}")
+            }
+            SyncError::Invalid(error) => write!(f, "invalid operation: {error}"),
+            SyncError::Storage(error) => write!(f, "storage error: {error}"),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for SyncError<E> {}
+
+impl<E> From<Error> for SyncError<E> {
+    fn from(error: Error) -> Self {
+        SyncError::Invalid(error)
+    }
+}
+
+/// The state of the sync protocol with a single peer.
+///
+/// The caller owns the [`SyncTarget`], and lends it to the session whenever a
+/// message arrives. Every message returned by the session must be sent to the
+/// peer, in order, over a transport that neither drops nor reorders them.
+/// After an error or a reconnect, the session is dropped and a new one starts
+/// over with a hello.
+#[derive(Debug, Clone)]
+pub struct Session {
+    phase: Phase,
+    peer: Option<ActorId>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            phase: Phase::Idle,
+            peer: None,
+        }
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Returns the actor of the peer, once its hello has been received.
+    pub fn peer(&self) -> Option<ActorId> {
+        self.peer
+    }
+
+    /// Starts the session by greeting the peer.
+    pub fn hello<T: DeltaCmRDT>(&mut self, replica: &Replica<T>) -> Message<T> {
+        if self.phase == Phase::Idle {
+            self.phase = Phase::AwaitingHello;
+        }
+        Message::Hello {
+            version: PROTOCOL_VERSION,
+            actor: replica.actor_id,
+            applied: replica.applied().clone(),
+        }
+    }
+
+    /// Returns the message that streams an operation just applied to the
+    /// replica, or `None` if the peer will receive it while catching up.
+    pub fn send<T: DeltaCmRDT>(&self, op: &T::Op, ctx: &AddCtx) -> Option<Message<T>> {
+        match self.phase {
+            Phase::Idle | Phase::AwaitingHello => None,
+            Phase::CatchingUp | Phase::Live => Some(Message::Op(op.clone(), ctx.clone())),
+        }
+    }
+
+    /// Handles a message from the peer, and returns the messages to send back.
+    ///
+    /// Operations received from the peer are validated and applied to the
+    /// target, unless they were already applied.
+    pub fn handle<T, S>(
+        &mut self,
+        target: &mut S,
+        message: Message<T>,
+    ) -> Result<Vec<Message<T>>, SyncError<S::Error>>
+    where
+        T: DeltaCmRDT,
+        S: SyncTarget<T>,
+    {
+        match (self.phase, message) {
+            (
+                Phase::Idle | Phase::AwaitingHello,
+                Message::Hello {
+                    version,
+                    actor,
+                    applied,
+                },
+            ) => {
+                if version != PROTOCOL_VERSION {
+                    return Err(SyncError::IncompatibleVersion(version));
+                }
+
+                let mut out = Vec::new();
+                if self.phase == Phase::Idle {
+                    out.push(self.hello(target.replica()));
+                }
+                // The peer's clock may cover operations it has only heard of,
+                // so only what it has applied without gaps is observed.
+                target.observe_peer(actor, applied.frontier());
+                let ops = target.ops_since(&applied).map_err(SyncError::Storage)?;
+                out.push(match ops {
+                    Some(ops) => Message::Ops(ops),
+                    None => {
+                        let (state, clock) = target.replica().delta_since_applied(&applied);
+                        Message::Delta { state, clock }
+                    }
+                });
+
+                self.peer = Some(actor);
+                self.phase = Phase::CatchingUp;
+                Ok(out)
+            }
+            (Phase::CatchingUp, Message::Ops(ops)) => {
+                for (op, ctx) in ops {
+                    target.try_apply_remote(op, ctx)?;
+                }
+                self.phase = Phase::Live;
+                Ok(Vec::new())
+            }
+            (Phase::CatchingUp, Message::Delta { state, clock }) => {
+                target
+                    .merge_delta(state, clock)
+                    .map_err(SyncError::Storage)?;
+                self.phase = Phase::Live;
+                Ok(Vec::new())
+            }
+            (Phase::Live, Message::Op(op, ctx)) => {
+                target.try_apply_remote(op, ctx)?;
+                Ok(Vec::new())
+            }
+            (phase, _) => Err(SyncError::UnexpectedMessage(phase)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{AppliedDots, ApplyOutcome};
+    use crate::or_set::{self, ORSet};
+    use crate::sync::{LoggedReplica, MemoryLog, NoLog, OpLog};
+    use std::collections::{BTreeSet, VecDeque};
+
+    type Set = ORSet<u32>;
+
+    /// A replica with its session with the other node.
+    struct Node<S> {
+        target: S,
+        session: Session,
+        outbox: VecDeque<Message<Set>>,
+    }
+
+    impl<L: OpLog<Set>> Node<LoggedReplica<Set, L>> {
+        fn new(actor: u64, log: L) -> Self {
+            let replica = Replica::new(ActorId(actor), ORSet::default());
+            Self::with_target(LoggedReplica::new(replica, log))
+        }
+
+        /// Applies a local op, records it and streams it if the session allows.
+        fn apply(&mut self, op: or_set::Op<u32>) {
+            let (op, ctx) = self.target.apply(op);
+            self.outbox.extend(self.session.send(&op, &ctx));
+        }
+    }
+
+    impl<S: SyncTarget<Set>> Node<S> {
+        fn with_target(target: S) -> Self {
+            Self {
+                target,
+                session: Session::new(),
+                outbox: VecDeque::new(),
+            }
+        }
+
+        fn replica(&self) -> &Replica<Set> {
+            self.target.replica()
+        }
+
+        fn connect(&mut self) {
+            let hello = self.session.hello(self.target.replica());
+            self.outbox.push_back(hello);
+        }
+
+        fn receive(&mut self, message: Message<Set>) -> Result<(), SyncError<S::Error>> {
+            let replies = self.session.handle(&mut self.target, message)?;
+            self.outbox.extend(replies);
+            Ok(())
+        }
+    }
+
+    /// Delivers messages both ways, through JSON, until both outboxes are empty.
+    fn pump<A, B>(a: &mut Node<A>, b: &mut Node<B>)
+    where
+        A: SyncTarget<Set, Error: fmt::Debug>,
+        B: SyncTarget<Set, Error: fmt::Debug>,
+    {
+        fn wire(message: Message<Set>) -> Message<Set> {
+            serde_json::from_slice(&serde_json::to_vec(&message).unwrap()).unwrap()
+        }
+
+        while !a.outbox.is_empty() || !b.outbox.is_empty() {
+            while let Some(message) = a.outbox.pop_front() {
+                b.receive(wire(message)).unwrap();
+            }
+            while let Some(message) = b.outbox.pop_front() {
+                a.receive(wire(message)).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_catch_up_with_ops_then_stream() {
+        // Arrange: both replicas made changes while apart.
+        let mut a = Node::new(1, MemoryLog::default());
+        let mut b = Node::new(2, MemoryLog::default());
+        a.apply(or_set::Op::Add(1));
+        a.apply(or_set::Op::Add(2));
+        b.apply(or_set::Op::Add(3));
+
+        // Act: only A initiates.
+        a.connect();
+        pump(&mut a, &mut b);
+
+        // Assert
+        assert_eq!(a.replica().read(), BTreeSet::from([1, 2, 3]));
+        assert_eq!(a.replica().state(), b.replica().state());
+        assert_eq!(a.session.phase(), Phase::Live);
+        assert_eq!(b.session.phase(), Phase::Live);
+        assert_eq!(b.session.peer(), Some(ActorId(1)));
+
+        // Act & Assert: live ops are streamed.
+        b.apply(or_set::Op::Rm(1));
+        pump(&mut a, &mut b);
+        assert_eq!(a.replica().read(), BTreeSet::from([2, 3]));
+    }
+
+    #[test]
+    fn test_catch_up_with_delta_without_log() {
+        // Arrange
+        let mut a = Node::new(1, NoLog);
+        let mut b = Node::new(2, NoLog);
+        a.apply(or_set::Op::Add(1));
+        b.apply(or_set::Op::Add(2));
+        b.apply(or_set::Op::Rm(2));
+
+        // Act: both sides initiate at once.
+        a.connect();
+        b.connect();
+        pump(&mut a, &mut b);
+
+        // Assert
+        assert_eq!(a.replica().read(), BTreeSet::from([1]));
+        assert_eq!(a.replica().state(), b.replica().state());
+        assert_eq!(a.replica().clock(), b.replica().clock());
+    }
+
+    #[test]
+    fn test_ops_during_handshake_are_not_lost() {
+        // Arrange: A applies an op after its hello is sent but before B's
+        // hello arrives, so it is not streamed.
+        let mut a = Node::new(1, MemoryLog::default());
+        let mut b = Node::new(2, MemoryLog::default());
+        a.connect();
+        a.apply(or_set::Op::Add(1));
+        assert_eq!(a.outbox.len(), 1);
+
+        // Act
+        pump(&mut a, &mut b);
+
+        // Assert
+        assert_eq!(b.replica().read(), BTreeSet::from([1]));
+    }
+
+    #[test]
+    fn test_ops_are_forwarded_to_third_replica() {
+        // Arrange: B learns of C's op, then syncs with A.
+        let mut c = Node::new(3, MemoryLog::default());
+        let mut b = Node::new(2, MemoryLog::default());
+        c.apply(or_set::Op::Add(7));
+        c.connect();
+        pump(&mut c, &mut b);
+        let mut a = Node::new(1, MemoryLog::default());
+        b.session = Session::new();
+
+        // Act
+        a.connect();
+        pump(&mut a, &mut b);
+
+        // Assert
+        assert_eq!(a.replica().read(), BTreeSet::from([7]));
+    }
+
+    #[test]
+    fn test_merged_state_falls_back_to_delta() {
+        // Arrange: B caught up with C through a delta, so its log has a gap.
+        let mut c = Node::new(3, NoLog);
+        let mut b = Node::new(2, MemoryLog::default());
+        c.apply(or_set::Op::Add(7));
+        c.connect();
+        pump(&mut c, &mut b);
+        let mut a = Node::new(1, MemoryLog::default());
+        b.session = Session::new();
+
+        // Act
+        a.connect();
+        let hello = a.outbox.pop_front().unwrap();
+        b.receive(hello).unwrap();
+
+        // Assert
+        assert!(matches!(b.outbox.back(), Some(Message::Delta { .. })));
+        pump(&mut a, &mut b);
+        assert_eq!(a.replica().read(), BTreeSet::from([7]));
+    }
+
+    #[test]
+    fn test_protocol_violations_are_rejected() {
+        let mut a = Node::new(1, NoLog);
+        let mut b = Node::new(2, NoLog);
+        let (op, ctx) = b.target.apply(or_set::Op::Add(1));
+
+        assert_eq!(
+            a.receive(Message::Op(op, ctx)),
+            Err(SyncError::UnexpectedMessage(Phase::Idle))
+        );
+        assert_eq!(
+            a.receive(Message::Hello {
+                version: PROTOCOL_VERSION + 1,
+                actor: ActorId(2),
+                applied: b.replica().applied().clone(),
+            }),
+            Err(SyncError::IncompatibleVersion(PROTOCOL_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn test_catch_up_sends_ops_the_peer_only_heard_of() {
+        // Arrange: C applied B's op, whose clock covers A's op, but not A's op.
+        let mut a = Node::new(1, MemoryLog::default());
+        let mut b = Node::new(2, MemoryLog::default());
+        let mut c = Node::new(3, MemoryLog::default());
+        let (op, ctx) = a.target.apply(or_set::Op::Add(1));
+        b.target.apply_remote(op, ctx);
+        let (op, ctx) = b.target.apply(or_set::Op::Add(2));
+        c.target.apply_remote(op, ctx);
+
+        // Act
+        c.connect();
+        pump(&mut c, &mut a);
+
+        // Assert: A does not take C's clock as proof that C applied its op.
+        assert_eq!(c.replica().read(), BTreeSet::from([1, 2]));
+        assert_eq!(a.replica().read(), BTreeSet::from([1, 2]));
+        assert_eq!(a.replica().peers()[&ActorId(3)].get(&ActorId(1)), 0);
+    }
+
+    #[test]
+    fn test_buffered_ops_are_recorded_once_released() {
+        // Arrange
+        let mut peer = Replica::new(ActorId(1), ORSet::default());
+        let first = peer.apply(or_set::Op::Add(1));
+        let second = peer.apply(or_set::Op::Add(2));
+        let replica = Replica::new(ActorId(2), ORSet::default()).with_causal_delivery();
+        let mut logged = LoggedReplica::new(replica, MemoryLog::default());
+        let everything = AppliedDots::default();
+
+        // Act & Assert: the buffered op is only recorded once it is applied.
+        let outcome = logged.try_apply_remote(second.0.clone(), second.1.clone());
+        assert_eq!(outcome, Ok(ApplyOutcome::Buffered));
+        assert_eq!(logged.log.ops_since(&everything), Some(Vec::new()));
+
+        logged
+            .try_apply_remote(first.0.clone(), first.1.clone())
+            .unwrap();
+        assert_eq!(logged.log.ops_since(&everything), Some(vec![first, second]));
+    }
+
+    #[test]
+    #[cfg(feature = "file-storage")]
+    fn test_sync_persistent_replica() {
+        use crate::storage::{FileStorage, PersistentReplica};
+
+        // Arrange: B keeps its replica in a directory.
+        let dir = tempfile::tempdir().unwrap();
+        let open = || {
+            let replica = Replica::new(ActorId(2), ORSet::default());
+            PersistentReplica::open(replica, FileStorage::open(dir.path()).unwrap()).unwrap()
+        };
+        let mut a = Node::new(1, MemoryLog::default());
+        let mut b = Node::with_target(open());
+        a.apply(or_set::Op::Add(1));
+        b.target.apply(or_set::Op::Add(2)).unwrap();
+
+        // Act
+        a.connect();
+        pump(&mut a, &mut b);
+        let (op, ctx) = a.target.apply(or_set::Op::Add(3));
+        b.receive(Message::Op(op, ctx)).unwrap();
+        drop(b);
+
+        // Assert: what B received survives a restart.
+        let restarted = open();
+        assert_eq!(restarted.replica().read(), BTreeSet::from([1, 2, 3]));
+        assert_eq!(a.replica().read(), restarted.replica().read());
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    core::{AddCtx, AppliedDots, CmRDT, DeltaCmRDT, InfallibleLocal},
+    g_set::{self, GSet},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// A Two-Phase Set, implemented as a composition of two op-based G-Sets.
+///
+/// Added values go into `adds`, and removed values into `removes`, which acts as
+/// a permanent tombstone set: once a value has been removed, adding it again has
+/// no effect. A value may be removed before it was ever added, which keeps it out
+/// of the set for good.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TwoPSet<T: Clone + Ord> {
+    pub adds: GSet<T>,
+    pub removes: GSet<T>,
+}
+
+/// Operations for a TwoPSet can add or permanently remove a value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op<T> {
+    Add(T),
+    Rm(T),
+}
+
+impl<T: Clone + Ord> Default for TwoPSet<T> {
+    fn default() -> Self {
+        Self {
+            adds: GSet {
+                values: BTreeSet::new(),
+            },
+            removes: GSet {
+                values: BTreeSet::new(),
+            },
+        }
+    }
+}
+
+impl<T: Clone + Ord> TwoPSet<T> {
+    /// Returns `true` if the value was added and never removed.
+    pub fn contains(&self, value: &T) -> bool {
+        self.adds.values.contains(value) && !self.removes.values.contains(value)
+    }
+}
+
+impl<T: Clone + Ord> CmRDT for TwoPSet<T> {
+    type Op = Op<T>;
+    type Value = BTreeSet<T>;
+
+    fn apply(&mut self, op: Self::Op, ctx: AddCtx) {
+        match op {
+            Op::Add(value) => self.adds.apply(g_set::Op::Add(value), ctx),
+            Op::Rm(value) => self.removes.apply(g_set::Op::Add(value), ctx),
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.adds.merge(other.adds);
+        self.removes.merge(other.removes);
+    }
+
+    fn read(&self) -> Self::Value {
+        self.adds
+            .values
+            .difference(&self.removes.values)
+            .cloned()
+            .collect()
+    }
+}
+
+impl<T: Clone + Ord> InfallibleLocal for TwoPSet<T> {}
+
+impl<T: Clone + Ord> DeltaCmRDT for TwoPSet<T> {
+    fn delta_since(&self, applied: &AppliedDots) -> Self {
+        Self {
+            adds: self.adds.delta_since(applied),
+            removes: self.removes.delta_since(applied),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ActorId, Replica};
+
+    #[test]
+    fn test_initial_value_is_empty() {
+        let replica = Replica::new(ActorId(1), TwoPSet::<i32>::default());
+        assert!(replica.read().is_empty());
+    }
+
+    #[test]
+    fn test_removal_is_permanent() {
+        // Arrange
+        let mut replica = Replica::new(ActorId(1), TwoPSet::default());
+
+        // Act
+        replica.apply(Op::Add("alice"));
+        replica.apply(Op::Add("bob"));
+        replica.apply(Op::Rm("alice"));
+        replica.apply(Op::Add("alice"));
+
+        // Assert
+        assert_eq!(replica.read(), BTreeSet::from(["bob"]));
+        assert!(!replica.state().contains(&"alice"));
+    }
+
+    #[test]
+    fn test_remove_before_add() {
+        let mut replica = Replica::new(ActorId(1), TwoPSet::default());
+        replica.apply(Op::Rm(1));
+        replica.apply(Op::Add(1));
+
+        assert!(replica.read().is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_add_loses_to_remove() {
+        let mut replica_a = Replica::new(ActorId(1), TwoPSet::default());
+        let mut replica_b = Replica::new(ActorId(2), TwoPSet::default());
+        let (op, ctx) = replica_a.apply(Op::Add(7));
+        replica_b.apply_remote(op, ctx);
+
+        let (rm, rm_ctx) = replica_a.apply(Op::Rm(7));
+        let (add, add_ctx) = replica_b.apply(Op::Add(7));
+        replica_a.apply_remote(add, add_ctx);
+        replica_b.apply_remote(rm, rm_ctx);
+
+        assert!(replica_a.read().is_empty());
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+}
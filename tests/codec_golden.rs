@@ -0,0 +1,127 @@
+use cmrdts::codec::{self, Decode, DeltaCtx, Encode};
+use cmrdts::core::{ActorId, AddCtx, Batch, Dot, Envelope, VClock};
+use cmrdts::{
+    bounded_counter, ew_flag, g_counter, lww_map, lww_register, or_map, or_set, pn_counter,
+    reset_counter, rga,
+};
+use std::fmt::Debug;
+use std::path::PathBuf;
+
+// The golden files pin the byte layout of `CODEC_VERSION`: a change that breaks
+// them breaks every frame already stored or in flight, and needs a new version.
+// Run with `CMRDTS_BLESS=1` to write the files for a newly added case, never to
+// update an existing one.
+fn assert_golden<V>(name: &str, value: V)
+where
+    V: Encode + Decode + PartialEq + Debug,
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.bin"));
+    let frame = codec::encode(&value);
+
+    if std::env::var_os("CMRDTS_BLESS").is_some() && !path.exists() {
+        std::fs::write(&path, &frame).unwrap();
+    }
+
+    let golden = std::fs::read(&path).unwrap_or_else(|_| panic!("missing {}", path.display()));
+    assert_eq!(frame, golden, "{name} no longer encodes to its golden file");
+    assert_eq!(codec::decode::<V>(&golden).unwrap(), value);
+}
+
+fn dot(actor: u64, counter: u64) -> Dot {
+    Dot {
+        actor: ActorId(actor),
+        counter,
+    }
+}
+
+fn ctx(actor: u64, counter: u64, prev: u64, clock: &[(u64, u64)]) -> AddCtx {
+    AddCtx {
+        dot: dot(actor, counter),
+        prev,
+        clock: VClock(clock.iter().map(|&(a, c)| (ActorId(a), c)).collect()),
+    }
+}
+
+#[test]
+fn test_core_types_golden() {
+    assert_golden("dot", dot(42, 7));
+    assert_golden(
+        "vclock",
+        VClock([(ActorId(1), 3), (ActorId(u64::MAX), 1 << 40)].into()),
+    );
+    assert_golden("add_ctx", ctx(2, 5, 2, &[(1, 4), (2, 5), (3, 1)]));
+    // A physical-clock timestamp: milliseconds shifted left by the logical bits.
+    assert_golden(
+        "add_ctx_hlc",
+        ctx(
+            9,
+            1_700_000_000_000 << 16,
+            1_699_999_999_000 << 16,
+            &[(9, 1_700_000_000_000 << 16)],
+        ),
+    );
+}
+
+#[test]
+fn test_counter_ops_golden() {
+    assert_golden("g_counter_inc", g_counter::Op::Inc(300));
+    assert_golden("pn_counter_dec", pn_counter::Op::Dec(5));
+    assert_golden("reset_counter_reset", reset_counter::Op::Reset);
+    assert_golden(
+        "bounded_counter_transfer",
+        bounded_counter::Op::Transfer(ActorId(8), 20),
+    );
+}
+
+#[test]
+fn test_register_and_flag_ops_golden() {
+    assert_golden("ew_flag_disable", ew_flag::Op::Disable);
+    assert_golden(
+        "lww_register_set",
+        lww_register::Op::Set(String::from("hello")),
+    );
+    assert_golden("lww_map_set", lww_map::Op::Set(String::from("key"), -12i64));
+}
+
+#[test]
+fn test_collection_ops_golden() {
+    assert_golden("or_set_rm", or_set::Op::Rm(String::from("apple")));
+    assert_golden(
+        "or_map_update",
+        or_map::Op::Update(String::from("votes"), pn_counter::Op::Inc(1)),
+    );
+    assert_golden(
+        "rga_insert_after",
+        rga::Op::InsertAfter(Some(dot(3, 11)), 'é'),
+    );
+    assert_golden("rga_delete", rga::Op::<char>::Delete(dot(3, 11)));
+}
+
+#[test]
+fn test_messages_golden() {
+    assert_golden(
+        "envelope",
+        Envelope::<or_set::ORSet<String>>::new(
+            or_set::Op::Add(String::from("pear")),
+            ctx(1, 2, 1, &[(1, 2), (2, 6)]),
+        ),
+    );
+    assert_golden(
+        "batch",
+        Batch::<pn_counter::PNCounter> {
+            ops: vec![pn_counter::Op::Inc(2), pn_counter::Op::Dec(1)],
+            ctx: ctx(1, 4, 3, &[(1, 5)]),
+        },
+    );
+    assert_golden(
+        "delta_ctx",
+        DeltaCtx {
+            dot: dot(1, 9),
+            prev: 8,
+            full: false,
+            changes: VClock([(ActorId(4), 0), (ActorId(7), 3)].into()),
+        },
+    );
+}
@@ -0,0 +1,205 @@
+use cmrdts::core::{ActorId, DeltaCmRDT, InfallibleLocal, Replica};
+use cmrdts::{
+    g_counter, g_set, lww_element_set, lww_map, lww_register, mv_register, or_map, or_set,
+    pn_counter, rga, two_p_set,
+};
+use std::fmt::Debug;
+
+// Builds two replicas that share some history and then diverge, and checks that
+// exchanging deltas converges them to the same state as exchanging full states.
+fn assert_delta_sync<T>(crdt: T, shared: Vec<T::Op>, ops_a: Vec<T::Op>, ops_b: Vec<T::Op>)
+where
+    T: DeltaCmRDT + InfallibleLocal + Clone + PartialEq + Debug,
+{
+    let mut replica_a = Replica::new(ActorId(1), crdt.clone());
+    let mut replica_b = Replica::new(ActorId(2), crdt);
+
+    for op in shared {
+        let (op, ctx) = replica_a.apply(op);
+        replica_b.apply_remote(op, ctx);
+    }
+    for op in ops_a {
+        replica_a.apply(op);
+    }
+    for op in ops_b {
+        replica_b.apply(op);
+    }
+
+    let mut expected = replica_a.clone();
+    expected.merge(replica_b.state().clone(), replica_b.clock().clone());
+
+    let (delta_for_b, clock_a) = replica_a.delta_since_applied(replica_b.applied());
+    let (delta_for_a, clock_b) = replica_b.delta_since_applied(replica_a.applied());
+    replica_a.merge_delta(delta_for_a, clock_b);
+    replica_b.merge_delta(delta_for_b, clock_a);
+
+    assert_eq!(replica_a.state(), expected.state());
+    assert_eq!(replica_b.state(), expected.state());
+}
+
+#[test]
+fn test_g_counter_delta_sync() {
+    use g_counter::Op::Inc;
+    assert_delta_sync(
+        g_counter::GCounter::default(),
+        vec![Inc(1), Inc(2)],
+        vec![Inc(3)],
+        vec![Inc(4), Inc(5)],
+    );
+}
+
+#[test]
+fn test_pn_counter_delta_sync() {
+    use pn_counter::Op::{Dec, Inc};
+    assert_delta_sync(
+        pn_counter::PNCounter::default(),
+        vec![Inc(10)],
+        vec![Dec(3)],
+        vec![Inc(4), Dec(1)],
+    );
+}
+
+#[test]
+fn test_g_set_delta_sync() {
+    use g_set::Op::Add;
+    assert_delta_sync(
+        g_set::GSet::default(),
+        vec![Add(1)],
+        vec![Add(2)],
+        vec![Add(3)],
+    );
+}
+
+#[test]
+fn test_lww_register_delta_sync() {
+    use lww_register::Op::Set;
+    assert_delta_sync(
+        lww_register::LWWRegister::default(),
+        vec![Set(1)],
+        vec![Set(2)],
+        vec![Set(3), Set(4)],
+    );
+}
+
+#[test]
+fn test_two_p_set_delta_sync() {
+    use two_p_set::Op::{Add, Rm};
+    assert_delta_sync(
+        two_p_set::TwoPSet::default(),
+        vec![Add(1), Add(2)],
+        vec![Rm(1)],
+        vec![Add(3), Rm(2)],
+    );
+}
+
+#[test]
+fn test_lww_element_set_delta_sync() {
+    use lww_element_set::Op::{Add, Rm};
+    assert_delta_sync(
+        lww_element_set::LWWElementSet::default(),
+        vec![Add(1), Add(2)],
+        vec![Rm(1), Add(3)],
+        vec![Add(1), Rm(2)],
+    );
+}
+
+#[test]
+fn test_lww_map_delta_sync() {
+    use lww_map::Op::{Remove, Set};
+    assert_delta_sync(
+        lww_map::LWWMap::default(),
+        vec![Set("a", 1), Set("b", 2)],
+        vec![Remove("a"), Set("c", 3)],
+        vec![Set("a", 4), Remove("b")],
+    );
+}
+
+#[test]
+fn test_mv_register_delta_sync() {
+    use mv_register::Op::Set;
+    assert_delta_sync(
+        mv_register::MVRegister::default(),
+        vec![Set(1)],
+        vec![Set(2)],
+        vec![Set(3)],
+    );
+}
+
+#[test]
+fn test_or_map_delta_sync() {
+    use or_map::Op::{Rm, Update};
+    use pn_counter::Op::{Dec, Inc};
+    assert_delta_sync(
+        or_map::ORMap::<_, pn_counter::PNCounter>::default(),
+        vec![Update(1, Inc(1)), Update(2, Inc(2))],
+        vec![Rm(1), Update(2, Dec(1))],
+        vec![Update(1, Inc(3)), Rm(2)],
+    );
+}
+
+#[test]
+fn test_or_set_delta_sync() {
+    use or_set::Op::{Add, Rm};
+    assert_delta_sync(
+        or_set::ORSet::default(),
+        vec![Add(1), Add(2)],
+        vec![Rm(1), Add(3)],
+        vec![Add(1), Rm(2)],
+    );
+}
+
+#[test]
+fn test_rga_delta_sync() {
+    use rga::Op::InsertAfter;
+    assert_delta_sync(
+        rga::Rga::default(),
+        vec![InsertAfter(None, 'a')],
+        vec![InsertAfter(None, 'b')],
+        vec![InsertAfter(None, 'c')],
+    );
+}
+
+#[test]
+fn test_delta_includes_ops_the_peer_only_heard_of() {
+    use g_counter::Op::Inc;
+
+    // Arrange: C applies B's op, whose clock also covers A's op that C never
+    // received.
+    let mut replica_a = Replica::new(ActorId(1), g_counter::GCounter::default());
+    let mut replica_b = Replica::new(ActorId(2), g_counter::GCounter::default());
+    let mut replica_c = Replica::new(ActorId(3), g_counter::GCounter::default());
+    let (op, ctx) = replica_a.apply(Inc(1));
+    replica_b.apply_remote(op, ctx);
+    let (op, ctx) = replica_b.apply(Inc(10));
+    replica_c.apply_remote(op, ctx);
+    assert!(replica_c.clock().descends(replica_a.clock()));
+
+    // Act
+    let (delta, clock) = replica_a.delta_since_applied(replica_c.applied());
+    replica_c.merge_delta(delta, clock);
+
+    // Assert
+    assert_eq!(replica_c.read(), 11);
+}
+
+#[test]
+fn test_delta_since_clock_syncs_causally_delivered_replicas() {
+    use or_set::Op::{Add, Rm};
+
+    // Arrange: B has applied every op A generated before diverging.
+    let mut replica_a = Replica::new(ActorId(1), or_set::ORSet::default());
+    let mut replica_b = Replica::new(ActorId(2), or_set::ORSet::default());
+    for op in [Add(1), Add(2)] {
+        let (op, ctx) = replica_a.apply(op);
+        replica_b.apply_remote(op, ctx);
+    }
+    replica_a.apply(Rm(1));
+    replica_a.apply(Add(3));
+
+    // Act
+    let (delta, clock) = replica_a.delta_since(replica_b.clock());
+    replica_b.merge_delta(delta, clock);
+
+    // Assert
+    assert_eq!(replica_b.state(), replica_a.state());
+}
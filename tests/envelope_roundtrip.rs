@@ -0,0 +1,137 @@
+use cmrdts::core::{ActorId, Envelope, FORMAT_VERSION, InfallibleLocal, Replica};
+use cmrdts::{
+    g_counter, g_set, lww_element_set, lww_map, lww_register, mv_register, or_map, or_set,
+    pn_counter, rga, two_p_set,
+};
+use serde::{Serialize, de::DeserializeOwned};
+use std::fmt::Debug;
+
+// Applies each op on a sending replica, sends it through JSON as an envelope,
+// and checks that the envelope survives unchanged and converges the receiver.
+fn assert_round_trip<T>(crdt: T, ops: Vec<T::Op>)
+where
+    T: InfallibleLocal + Clone + PartialEq + Debug,
+    T::Op: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let mut sender = Replica::new(ActorId(1), crdt.clone());
+    let mut receiver = Replica::new(ActorId(2), crdt);
+
+    for op in ops {
+        let envelope = Envelope::<T>::from(sender.apply(op));
+        assert_eq!(envelope.version, FORMAT_VERSION);
+
+        let bytes = serde_json::to_vec(&envelope).unwrap();
+        let decoded: Envelope<T> = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, envelope);
+
+        let (op, ctx) = decoded.into_parts();
+        receiver.apply_remote(op, ctx);
+    }
+
+    assert_eq!(receiver.state(), sender.state());
+}
+
+#[test]
+fn test_g_counter_round_trip() {
+    assert_round_trip(
+        g_counter::GCounter::default(),
+        vec![g_counter::Op::Inc(3), g_counter::Op::Inc(4)],
+    );
+}
+
+#[test]
+fn test_pn_counter_round_trip() {
+    assert_round_trip(
+        pn_counter::PNCounter::default(),
+        vec![pn_counter::Op::Inc(10), pn_counter::Op::Dec(3)],
+    );
+}
+
+#[test]
+fn test_g_set_round_trip() {
+    assert_round_trip(
+        g_set::GSet::default(),
+        vec![
+            g_set::Op::Add("a".to_string()),
+            g_set::Op::Add("b".to_string()),
+        ],
+    );
+}
+
+#[test]
+fn test_two_p_set_round_trip() {
+    assert_round_trip(
+        two_p_set::TwoPSet::default(),
+        vec![two_p_set::Op::Add(1), two_p_set::Op::Rm(1)],
+    );
+}
+
+#[test]
+fn test_lww_element_set_round_trip() {
+    assert_round_trip(
+        lww_element_set::LWWElementSet::default(),
+        vec![lww_element_set::Op::Add(1), lww_element_set::Op::Rm(1)],
+    );
+}
+
+#[test]
+fn test_lww_register_round_trip() {
+    assert_round_trip(
+        lww_register::LWWRegister::default(),
+        vec![lww_register::Op::Set(1), lww_register::Op::Set(2)],
+    );
+}
+
+#[test]
+fn test_lww_map_round_trip() {
+    assert_round_trip(
+        lww_map::LWWMap::default(),
+        vec![
+            lww_map::Op::Set("a".to_string(), 1),
+            lww_map::Op::Remove("a".to_string()),
+        ],
+    );
+}
+
+#[test]
+fn test_mv_register_round_trip() {
+    assert_round_trip(
+        mv_register::MVRegister::default(),
+        vec![mv_register::Op::Set(true), mv_register::Op::Set(false)],
+    );
+}
+
+#[test]
+fn test_or_map_round_trip() {
+    assert_round_trip(
+        or_map::ORMap::<_, g_set::GSet<_>>::default(),
+        vec![
+            or_map::Op::Update(1, g_set::Op::Add('a')),
+            or_map::Op::Rm(1),
+        ],
+    );
+}
+
+#[test]
+fn test_or_set_round_trip() {
+    assert_round_trip(
+        or_set::ORSet::default(),
+        vec![or_set::Op::Add(1), or_set::Op::Add(2), or_set::Op::Rm(1)],
+    );
+}
+
+#[test]
+fn test_rga_round_trip() {
+    // The sender assigns the same dot to its first insert as this replica does.
+    let mut replica = Replica::new(ActorId(1), rga::Rga::default());
+    let (_, ctx) = replica.apply(rga::Op::InsertAfter(None, 'a'));
+
+    assert_round_trip(
+        rga::Rga::default(),
+        vec![
+            rga::Op::InsertAfter(None, 'a'),
+            rga::Op::InsertAfter(Some(ctx.dot), 'b'),
+            rga::Op::Delete(ctx.dot),
+        ],
+    );
+}
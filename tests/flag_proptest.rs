@@ -0,0 +1,126 @@
+use cmrdts::core::{ActorId, InfallibleLocal, Replica};
+use cmrdts::dw_flag::DWFlag;
+use cmrdts::ew_flag::{EWFlag, Op};
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+use std::fmt::Debug;
+
+// A strategy to generate a single random Op.
+fn arb_op() -> impl Strategy<Value = Op> {
+    prop_oneof![Just(Op::Enable), Just(Op::Disable)]
+}
+
+// A strategy to generate a vector of random operations.
+fn arb_ops() -> impl Strategy<Value = Vec<Op>> {
+    prop::collection::vec(arb_op(), 0..15)
+}
+
+// Checks the merge laws of a flag type, and that three replicas that never
+// observed each other read `expected`, given the last toggle of each.
+fn check_flag_properties<T>(
+    ops: [Vec<Op>; 3],
+    expected: impl Fn(&[Option<&Op>]) -> bool,
+) -> Result<(), TestCaseError>
+where
+    T: InfallibleLocal<Op = Op, Value = bool> + Default + Clone + PartialEq + Debug,
+{
+    // --- Arrange ---
+    let mut replicas = Vec::new();
+    for (id, ops) in (1..).zip(&ops) {
+        let mut replica = Replica::new(ActorId(id), T::default());
+        for op in ops {
+            replica.apply(*op);
+        }
+        replicas.push(replica);
+    }
+    let [replica_a, replica_b, replica_c] = &replicas[..] else {
+        unreachable!()
+    };
+
+    // --- Act & Assert ---
+
+    // 1. Test Commutativity
+    {
+        let mut merged_ab = replica_a.clone();
+        merged_ab.merge(replica_b.state().clone(), replica_b.clock().clone());
+
+        let mut merged_ba = replica_b.clone();
+        merged_ba.merge(replica_a.state().clone(), replica_a.clock().clone());
+
+        prop_assert_eq!(merged_ab.state(), merged_ba.state(), "Commutativity failed");
+    }
+
+    // 2. Test Associativity
+    {
+        let mut merged_ab = replica_a.clone();
+        merged_ab.merge(replica_b.state().clone(), replica_b.clock().clone());
+        let mut merged_ab_c = merged_ab;
+        merged_ab_c.merge(replica_c.state().clone(), replica_c.clock().clone());
+
+        let mut merged_bc = replica_b.clone();
+        merged_bc.merge(replica_c.state().clone(), replica_c.clock().clone());
+        let mut merged_a_bc = replica_a.clone();
+        merged_a_bc.merge(merged_bc.state().clone(), merged_bc.clock().clone());
+
+        prop_assert_eq!(
+            merged_ab_c.state(),
+            merged_a_bc.state(),
+            "Associativity failed"
+        );
+    }
+
+    // 3. Test Idempotence
+    {
+        let mut idempotent_a = replica_a.clone();
+        idempotent_a.merge(replica_a.state().clone(), replica_a.clock().clone());
+        prop_assert_eq!(
+            idempotent_a.state(),
+            replica_a.state(),
+            "Idempotence failed"
+        );
+    }
+
+    // 4. Test Correctness of final value
+    {
+        let last: Vec<_> = ops.iter().map(|ops| ops.last()).collect();
+
+        let mut final_replica = replica_a.clone();
+        final_replica.merge(replica_b.state().clone(), replica_b.clock().clone());
+        final_replica.merge(replica_c.state().clone(), replica_c.clock().clone());
+
+        prop_assert_eq!(
+            final_replica.read(),
+            expected(&last),
+            "Final value calculation is incorrect"
+        );
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+    #[test]
+    fn test_ew_flag_properties(
+        ops_a in arb_ops(),
+        ops_b in arb_ops(),
+        ops_c in arb_ops()
+    ) {
+        // The flag is enabled if any replica's last toggle enabled it.
+        check_flag_properties::<EWFlag>([ops_a, ops_b, ops_c], |last| {
+            last.contains(&Some(&Op::Enable))
+        })?;
+    }
+
+    #[test]
+    fn test_dw_flag_properties(
+        ops_a in arb_ops(),
+        ops_b in arb_ops(),
+        ops_c in arb_ops()
+    ) {
+        // The flag is enabled only if some replica's last toggle enabled it and
+        // none disabled it.
+        check_flag_properties::<DWFlag>([ops_a, ops_b, ops_c], |last| {
+            last.contains(&Some(&Op::Enable)) && !last.contains(&Some(&Op::Disable))
+        })?;
+    }
+}
@@ -0,0 +1,108 @@
+use cmrdts::core::{ActorId, AddCtx, CmRDT, Replica};
+use cmrdts::g_counter::{GCounter, Op};
+use proptest::prelude::*;
+
+// Proptest strategy to generate a vector of random increment amounts.
+fn arb_ops() -> impl Strategy<Value = Vec<u64>> {
+    prop::collection::vec(1..100u64, 0..15)
+}
+
+// Applies the increments on the replica and returns the generated ops.
+fn apply_all(replica: &mut Replica<GCounter>, amounts: Vec<u64>) -> Vec<(Op, AddCtx)> {
+    amounts
+        .into_iter()
+        .map(|amount| replica.apply(Op::Inc(amount)))
+        .collect()
+}
+
+// Syncs every replica with every other one through full-state merges.
+fn sync_all(replicas: &mut [Replica<GCounter>]) {
+    let snapshots: Vec<_> = replicas
+        .iter()
+        .map(|r| (r.state().clone(), r.clock().clone()))
+        .collect();
+    for replica in replicas.iter_mut() {
+        for (state, clock) in &snapshots {
+            replica.merge(state.clone(), clock.clone());
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+    #[test]
+    fn test_g_counter_compaction_properties(
+        stable_ops in [arb_ops(), arb_ops(), arb_ops()],
+        later_ops in [arb_ops(), arb_ops(), arb_ops()],
+    ) {
+        // --- Arrange ---
+        // Phase 1: every replica increments and then everyone syncs, so every
+        // event up to this point is causally stable.
+        let mut replicas: Vec<_> = (1..=3)
+            .map(|id| Replica::new(ActorId(id), GCounter::default()))
+            .collect();
+        let mut stable_log = Vec::new();
+        for (replica, amounts) in replicas.iter_mut().zip(stable_ops) {
+            stable_log.extend(apply_all(replica, amounts));
+        }
+        sync_all(&mut replicas);
+        let stable = replicas[0].clock().clone();
+
+        // Phase 2: every replica keeps incrementing without syncing.
+        for (replica, amounts) in replicas.iter_mut().zip(later_ops) {
+            apply_all(replica, amounts);
+        }
+
+        let [a, b, c] = [0, 1, 2].map(|i| replicas[i].state().clone());
+        let mut compacted_a = a.clone();
+        compacted_a.compact(&stable);
+
+        // --- Act & Assert ---
+
+        // 1. Test that compaction does not change the value
+        prop_assert_eq!(compacted_a.read(), a.read(), "Compaction changed the value");
+
+        // 2. Test that re-delivered stable ops are ignored after compaction
+        {
+            let mut redelivered = compacted_a.clone();
+            for (op, ctx) in stable_log.iter().cloned() {
+                redelivered.apply(op, ctx);
+            }
+            prop_assert_eq!(&redelivered, &compacted_a, "Folded dot was applied again");
+        }
+
+        // 3. Test that merging with a compacted state converges to the same value
+        {
+            let mut expected = a.clone();
+            expected.merge(b.clone());
+            expected.merge(c.clone());
+
+            let mut merged_abc = compacted_a.clone();
+            merged_abc.merge(b.clone());
+            merged_abc.merge(c.clone());
+
+            let mut merged_cba = c.clone();
+            merged_cba.merge(b.clone());
+            merged_cba.merge(compacted_a.clone());
+
+            prop_assert_eq!(merged_abc.read(), expected.read(), "Merge after compaction diverged");
+            prop_assert_eq!(&merged_abc, &merged_cba, "Commutativity failed after compaction");
+        }
+
+        // 4. Test that replicas compacted independently still converge
+        {
+            let mut compacted_b = b.clone();
+            compacted_b.compact(&stable);
+
+            let mut merged_ab = compacted_a.clone();
+            merged_ab.merge(compacted_b.clone());
+            let mut merged_ba = compacted_b;
+            merged_ba.merge(compacted_a.clone());
+            let mut merged_uncompacted = a.clone();
+            merged_uncompacted.merge(b.clone());
+
+            prop_assert_eq!(&merged_ab, &merged_ba, "Commutativity failed between compacted states");
+            prop_assert_eq!(merged_ab.read(), merged_uncompacted.read(), "Compacted states diverged");
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use cmrdts::core::{ActorId, Replica};
+use cmrdts::or_set::{ORSet, Op};
+use proptest::prelude::*;
+
+// A strategy to generate a single random Op over a small domain, so that adds
+// and removes frequently target the same element.
+fn arb_op() -> impl Strategy<Value = Op<u8>> {
+    prop_oneof![(0..5u8).prop_map(Op::Add), (0..5u8).prop_map(Op::Rm),]
+}
+
+// A strategy to generate a vector of random operations.
+fn arb_ops() -> impl Strategy<Value = Vec<Op<u8>>> {
+    prop::collection::vec(arb_op(), 0..15)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+    #[test]
+    fn test_or_set_properties(
+        ops_a in arb_ops(),
+        ops_b in arb_ops(),
+        ops_c in arb_ops()
+    ) {
+        // --- Arrange ---
+        let mut replica_a = Replica::new(ActorId(1), ORSet::default());
+        let mut log_a = Vec::new();
+        for op in ops_a {
+            log_a.push(replica_a.apply(op));
+        }
+
+        let mut replica_b = Replica::new(ActorId(2), ORSet::default());
+        let mut log_b = Vec::new();
+        for op in ops_b {
+            log_b.push(replica_b.apply(op));
+        }
+
+        let mut replica_c = Replica::new(ActorId(3), ORSet::default());
+        let mut log_c = Vec::new();
+        for op in ops_c {
+            log_c.push(replica_c.apply(op));
+        }
+
+        // --- Act & Assert ---
+
+        // 1. Test Commutativity
+        {
+            let mut merged_ab = replica_a.clone();
+            merged_ab.merge(replica_b.state().clone(), replica_b.clock().clone());
+
+            let mut merged_ba = replica_b.clone();
+            merged_ba.merge(replica_a.state().clone(), replica_a.clock().clone());
+
+            prop_assert_eq!(merged_ab.state(), merged_ba.state(), "Commutativity failed");
+        }
+
+        // 2. Test Associativity
+        {
+            let mut merged_ab = replica_a.clone();
+            merged_ab.merge(replica_b.state().clone(), replica_b.clock().clone());
+            let mut merged_ab_c = merged_ab;
+            merged_ab_c.merge(replica_c.state().clone(), replica_c.clock().clone());
+
+            let mut merged_bc = replica_b.clone();
+            merged_bc.merge(replica_c.state().clone(), replica_c.clock().clone());
+            let mut merged_a_bc = replica_a.clone();
+            merged_a_bc.merge(merged_bc.state().clone(), merged_bc.clock().clone());
+
+            prop_assert_eq!(merged_ab_c.state(), merged_a_bc.state(), "Associativity failed");
+        }
+
+        // 3. Test Idempotence
+        {
+            let mut idempotent_a = replica_a.clone();
+            idempotent_a.merge(replica_a.state().clone(), replica_a.clock().clone());
+            prop_assert_eq!(idempotent_a.state(), replica_a.state(), "Idempotence failed");
+        }
+
+        // 4. Test Convergence of op-based delivery, regardless of delivery order
+        {
+            let mut forward = Replica::new(ActorId(4), ORSet::default());
+            for (op, ctx) in log_a.iter().chain(log_b.iter()).chain(log_c.iter()) {
+                forward.apply_remote(op.clone(), ctx.clone());
+            }
+
+            let mut reversed = Replica::new(ActorId(5), ORSet::default());
+            for (op, ctx) in log_c.iter().chain(log_b.iter()).chain(log_a.iter()).rev() {
+                reversed.apply_remote(op.clone(), ctx.clone());
+            }
+
+            let mut merged = replica_a.clone();
+            merged.merge(replica_b.state().clone(), replica_b.clock().clone());
+            merged.merge(replica_c.state().clone(), replica_c.clock().clone());
+
+            prop_assert_eq!(forward.state(), reversed.state(), "Delivery order changed the result");
+            prop_assert_eq!(forward.state(), merged.state(), "Op-based and state-based sync diverged");
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use cmrdts::core::{ActorId, AddCtx, Replica};
+use cmrdts::reset_counter::{Op, ResetCounter};
+use proptest::prelude::*;
+
+// A strategy to generate a single random Op, with resets common enough to
+// interleave with the increments and decrements of other replicas.
+fn arb_op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        2 => (1..50u64).prop_map(Op::Inc),
+        2 => (1..50u64).prop_map(Op::Dec),
+        1 => Just(Op::Reset),
+    ]
+}
+
+// A strategy to generate the steps of a run: which replica issues an op, and
+// which of the three replicas receive it right away.
+fn arb_steps() -> impl Strategy<Value = Vec<(usize, Op, [bool; 3])>> {
+    prop::collection::vec((0..3usize, arb_op(), any::<[bool; 3]>()), 0..30)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+    #[test]
+    fn test_reset_counter_converges(steps in arb_steps()) {
+        // --- Arrange ---
+        let mut replicas: Vec<_> = (1..=3)
+            .map(|id| Replica::new(ActorId(id), ResetCounter::default()))
+            .collect();
+        let mut log: Vec<(Op, AddCtx)> = Vec::new();
+
+        // --- Act ---
+        // Each op is delivered immediately to some replicas only, so resets are
+        // issued with partial knowledge of the other replicas' ops.
+        for (issuer, op, delivered_to) in steps {
+            let (op, ctx) = replicas[issuer].apply(op);
+            for (i, replica) in replicas.iter_mut().enumerate() {
+                if i != issuer && delivered_to[i] {
+                    replica.apply_remote(op, ctx.clone());
+                }
+            }
+            log.push((op, ctx));
+        }
+
+        // Deliver everything that is missing, in reverse order.
+        for replica in replicas.iter_mut() {
+            for (op, ctx) in log.iter().rev() {
+                replica.apply_remote(*op, ctx.clone());
+            }
+        }
+
+        // --- Assert ---
+        // 1. Every replica converges to the same state.
+        prop_assert_eq!(replicas[0].state(), replicas[1].state(), "Replicas diverged");
+        prop_assert_eq!(replicas[1].state(), replicas[2].state(), "Replicas diverged");
+
+        // 2. Exactly the ops that no reset observed are counted.
+        let expected: i64 = log
+            .iter()
+            .filter(|(_, ctx)| {
+                !log.iter()
+                    .any(|(op, reset)| *op == Op::Reset && reset.clock.contains(&ctx.dot))
+            })
+            .map(|(op, _)| match op {
+                Op::Inc(amount) => *amount as i64,
+                Op::Dec(amount) => -(*amount as i64),
+                Op::Reset => 0,
+            })
+            .sum();
+        prop_assert_eq!(replicas[0].read(), expected, "Final value calculation is incorrect");
+
+        // 3. State-based merge agrees with op-based delivery.
+        let mut merged = Replica::new(ActorId(4), ResetCounter::default());
+        for replica in &replicas {
+            merged.merge(replica.state().clone(), replica.clock().clone());
+        }
+        prop_assert_eq!(merged.state(), replicas[0].state(), "Op-based and state-based sync diverged");
+    }
+}